@@ -1,13 +1,152 @@
-use {Result as Res, Controller};
+//! Calibration.
+//!
+//! The firmware calibration can be triggered but there's no known request to
+//! read it back or to write it, so the calibration stored on the controller
+//! can't be inspected, saved or restored.
+//!
+//! Drift can instead be corrected in software: offsets are measured while the
+//! controller rests flat, can be saved to a file, and are only subtracted from
+//! the states passed to `Calibration::apply`.
+
+use std::io::{Read, Write, BufRead, BufReader};
+use std::fs::File;
+use std::path::Path;
+use std::time::Duration;
+use {Result as Res, Error, Controller, Transport, State, Axis, Vector};
+use button::{PAD_TOUCH, TRACK_TOUCH};
+use settings::{self, Register};
+
+/// Offsets subtracted from the input states.
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct Calibration {
+	/// The left trackpad center.
+	pub left: Axis,
+
+	/// The right trackpad center.
+	pub right: Axis,
+
+	/// The analog stick center.
+	pub stick: Axis,
+
+	/// The accelerometer bias, in g.
+	pub acceleration: Vector,
+
+	/// The gyroscope bias, in degrees per second.
+	pub rotation: Vector,
+}
+
+impl Calibration {
+	/// Parse a calibration, one offset per line as name followed by the
+	/// values.
+	pub fn parse<R: Read>(input: R) -> Res<Calibration> {
+		let mut calibration = Calibration::default();
+
+		for line in BufReader::new(input).lines() {
+			let line = try!(line);
+			let line = line.split('#').next().unwrap().trim();
+
+			if line.is_empty() {
+				continue;
+			}
+
+			let mut parts = line.split_whitespace();
+			let     name  = parts.next().unwrap();
+			let     rest  = parts.collect::<Vec<_>>();
+
+			fn axis(values: &[&str]) -> Res<Axis> {
+				if values.len() != 2 {
+					return Err(Error::InvalidParameter);
+				}
+
+				Ok(Axis {
+					x: try!(values[0].parse().map_err(|_| Error::InvalidParameter)),
+					y: try!(values[1].parse().map_err(|_| Error::InvalidParameter)),
+				})
+			}
+
+			fn vector(values: &[&str]) -> Res<Vector> {
+				if values.len() != 3 {
+					return Err(Error::InvalidParameter);
+				}
+
+				Ok(Vector {
+					x: try!(values[0].parse().map_err(|_| Error::InvalidParameter)),
+					y: try!(values[1].parse().map_err(|_| Error::InvalidParameter)),
+					z: try!(values[2].parse().map_err(|_| Error::InvalidParameter)),
+				})
+			}
+
+			match name {
+				"left"         => calibration.left         = try!(axis(&rest)),
+				"right"        => calibration.right        = try!(axis(&rest)),
+				"stick"        => calibration.stick        = try!(axis(&rest)),
+				"acceleration" => calibration.acceleration = try!(vector(&rest)),
+				"rotation"     => calibration.rotation     = try!(vector(&rest)),
+
+				_ =>
+					return Err(Error::InvalidParameter)
+			}
+		}
+
+		Ok(calibration)
+	}
+
+	/// Load a calibration from the given path.
+	pub fn load<P: AsRef<Path>>(path: P) -> Res<Calibration> {
+		Calibration::parse(try!(File::open(path)))
+	}
+
+	/// Write the calibration in the format understood by `parse`.
+	pub fn write<W: Write>(&self, mut output: W) -> Res<()> {
+		try!(writeln!(output, "left {} {}", self.left.x, self.left.y));
+		try!(writeln!(output, "right {} {}", self.right.x, self.right.y));
+		try!(writeln!(output, "stick {} {}", self.stick.x, self.stick.y));
+		try!(writeln!(output, "acceleration {} {} {}", self.acceleration.x, self.acceleration.y, self.acceleration.z));
+		try!(writeln!(output, "rotation {} {} {}", self.rotation.x, self.rotation.y, self.rotation.z));
+
+		Ok(())
+	}
+
+	/// Save the calibration to the given path.
+	pub fn save<P: AsRef<Path>>(&self, path: P) -> Res<()> {
+		self.write(try!(File::create(path)))
+	}
+
+	/// Subtract the offsets from an input state.
+	pub fn apply(&self, state: &mut State) {
+		fn offset(axis: &mut Axis, by: Axis) {
+			axis.x = axis.x.saturating_sub(by.x);
+			axis.y = axis.y.saturating_sub(by.y);
+		}
+
+		fn bias(vector: &mut Vector, by: Vector) {
+			vector.x -= by.x;
+			vector.y -= by.y;
+			vector.z -= by.z;
+		}
+
+		if let &mut State::Input { buttons, ref mut pad, ref mut acceleration, ref mut rotation, .. } = state {
+			// The left pad and the stick share the same axis.
+			offset(&mut pad.left, if buttons.contains(PAD_TOUCH) { self.left } else { self.stick });
+
+			if buttons.contains(TRACK_TOUCH) {
+				offset(&mut pad.right, self.right);
+			}
+
+			bias(acceleration, self.acceleration);
+			bias(rotation, self.rotation);
+		}
+	}
+}
 
 /// Calibration manager.
-pub struct Calibrate<'a, 'b: 'a> {
-	controller: &'a mut Controller<'b>,
+pub struct Calibrate<'a, T: Transport + 'a> {
+	controller: &'a mut Controller<T>,
 }
 
-impl<'a, 'b> Calibrate<'a, 'b> {
+impl<'a, T: Transport> Calibrate<'a, T> {
 	#[doc(hidden)]
-	pub fn new(controller: &'a mut Controller<'b>) -> Calibrate<'a, 'b> {
+	pub fn new(controller: &'a mut Controller<T>) -> Calibrate<'a, T> {
 		Calibrate {
 			controller: controller,
 		}
@@ -27,4 +166,143 @@ impl<'a, 'b> Calibrate<'a, 'b> {
 	pub fn sensors(self) -> Res<()> {
 		self.controller.control(0xb5)
 	}
+
+	/// Measure the stick center and the sensors bias from the given number of
+	/// samples, the controller has to rest flat and untouched.
+	pub fn rest(self, samples: usize) -> Res<Calibration> {
+		let imu = self.controller.shadow().get(Register::Imu).unwrap_or(0);
+		try!(self.controller.settings().imu(settings::ORIENTATION | settings::ACCELERATION | settings::GYROSCOPE));
+
+		let result = measure(self.controller, samples);
+		try!(self.controller.settings().set(Register::Imu, imu));
+
+		result
+	}
+}
+
+fn measure<T: Transport>(controller: &mut Controller<T>, samples: usize) -> Res<Calibration> {
+	if samples == 0 {
+		return Err(Error::InvalidParameter);
+	}
+
+	let mut stick        = (0i64, 0i64);
+	let mut acceleration = Vector::default();
+	let mut rotation     = Vector::default();
+	let mut count        = 0;
+
+	while count < samples {
+		if let State::Input { buttons, pad, acceleration: a, rotation: r, .. } = try!(controller.state(Duration::from_secs(1))) {
+			if buttons.intersects(PAD_TOUCH | TRACK_TOUCH) {
+				continue;
+			}
+
+			stick.0 += pad.left.x as i64;
+			stick.1 += pad.left.y as i64;
+
+			acceleration.x += a.x;
+			acceleration.y += a.y;
+			acceleration.z += a.z;
+
+			rotation.x += r.x;
+			rotation.y += r.y;
+			rotation.z += r.z;
+
+			count += 1;
+		}
+	}
+
+	let n = count as f32;
+	let (ax, ay, az) = (acceleration.x / n, acceleration.y / n, acceleration.z / n);
+
+	// Resting flat gravity is only on the Z axis, anything else is bias.
+	let gravity = if az < 0.0 { -1.0 } else { 1.0 };
+
+	Ok(Calibration {
+		stick: Axis {
+			x: (stick.0 / count as i64) as i16,
+			y: (stick.1 / count as i64) as i16,
+		},
+
+		acceleration: Vector {
+			x: ax,
+			y: ay,
+			z: az - gravity,
+		},
+
+		rotation: Vector {
+			x: rotation.x / n,
+			y: rotation.y / n,
+			z: rotation.z / n,
+		},
+
+		.. Default::default()
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+	use byteorder::{WriteBytesExt, LittleEndian};
+	use transport::Mock;
+	use {Controller, State, Axis};
+	use super::Calibration;
+
+	fn input(stick: (i16, i16), acceleration: i16, rotation: i16) -> Vec<u8> {
+		let mut packet = vec![0u8; 12];
+		packet.write_i16::<LittleEndian>(stick.0).unwrap();
+		packet.write_i16::<LittleEndian>(stick.1).unwrap();
+		packet.resize(24, 0);
+
+		for &value in &[0, 0, acceleration, rotation, 0, 0] {
+			packet.write_i16::<LittleEndian>(value).unwrap();
+		}
+
+		packet.resize(44, 0);
+		packet
+	}
+
+	#[test]
+	fn rest() {
+		let mut controller = Controller::new(Mock::new(), 0x1102).unwrap();
+
+		controller.transport_mut()
+			.input(0x01, &input((90, -40), 20480, 16))
+			.input(0x01, &input((110, -60), 20480, 16))
+			.input(0x01, &input((110, -60), 20480, 16));
+
+		let calibration = controller.calibrate().rest(2).unwrap();
+		assert_eq!(Axis { x: 100, y: -50 }, calibration.stick);
+		assert_eq!(1.0, calibration.rotation.x);
+		assert!((calibration.acceleration.z - 0.25).abs() < 0.001);
+
+		// The sensors are turned back off.
+		assert_eq!(Some(0), controller.shadow().get(::settings::Register::Imu));
+
+		// States are only calibrated when asked.
+		let mut state = controller.state(Duration::from_secs(0)).unwrap();
+
+		match state {
+			State::Input { pad, .. } =>
+				assert_eq!(Axis { x: 110, y: -60 }, pad.left),
+
+			_ => panic!()
+		}
+
+		calibration.apply(&mut state);
+
+		match state {
+			State::Input { pad, acceleration, rotation, .. } => {
+				assert_eq!(Axis { x: 10, y: -10 }, pad.left);
+				assert_eq!(0.0, rotation.x);
+				assert!((acceleration.z - 1.0).abs() < 0.001);
+			}
+
+			_ => panic!()
+		}
+
+		let mut saved = Vec::new();
+		calibration.write(&mut saved).unwrap();
+		assert_eq!(calibration, Calibration::parse(&saved[..]).unwrap());
+		assert!(Calibration::parse(&b"stick 1"[..]).is_err());
+	}
 }
@@ -0,0 +1,297 @@
+//! Recording and replay of raw controller frames.
+//!
+//! A capture file starts with the `steamy` magic followed by a version byte,
+//! then every frame is stored as a little endian `u64` timestamp in
+//! microseconds since the start of the capture, the frame ID, the payload
+//! size and the payload itself.
+
+use std::io::{self, Read, Write};
+use std::fs::File;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+use byteorder::{ReadBytesExt, WriteBytesExt, LittleEndian};
+use {Result as Res, Error, Transport};
+
+const MAGIC:   &'static [u8] = b"steamy";
+const VERSION: u8            = 1;
+
+/// A captured frame.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Frame {
+	/// When the frame was received, relative to the start of the capture.
+	pub at: Duration,
+
+	/// The frame ID.
+	pub id: u8,
+
+	/// The frame payload.
+	pub payload: Vec<u8>,
+}
+
+fn micros(value: Duration) -> u64 {
+	value.as_secs() * 1_000_000 + value.subsec_nanos() as u64 / 1_000
+}
+
+/// Capture writer.
+pub struct Writer<W: Write> {
+	stream: W,
+	start:  Instant,
+}
+
+impl<W: Write> Writer<W> {
+	/// Create a capture writer, the capture starts now.
+	pub fn new(mut stream: W) -> Res<Writer<W>> {
+		try!(stream.write_all(MAGIC));
+		try!(stream.write_u8(VERSION));
+
+		Ok(Writer {
+			stream: stream,
+			start:  Instant::now(),
+		})
+	}
+
+	/// Record a frame as received now.
+	pub fn write(&mut self, id: u8, payload: &[u8]) -> Res<()> {
+		let at = self.start.elapsed();
+
+		self.frame(&Frame {
+			at:      at,
+			id:      id,
+			payload: payload.to_vec(),
+		})
+	}
+
+	/// Record the given frame.
+	pub fn frame(&mut self, frame: &Frame) -> Res<()> {
+		if frame.payload.len() > 60 {
+			return Err(Error::InvalidParameter);
+		}
+
+		try!(self.stream.write_u64::<LittleEndian>(micros(frame.at)));
+		try!(self.stream.write_u8(frame.id));
+		try!(self.stream.write_u8(frame.payload.len() as u8));
+		try!(self.stream.write_all(&frame.payload));
+
+		Ok(())
+	}
+
+	/// Flush the underlying stream.
+	pub fn flush(&mut self) -> Res<()> {
+		Ok(try!(self.stream.flush()))
+	}
+}
+
+/// Capture reader.
+pub struct Reader<R: Read> {
+	stream: R,
+}
+
+impl<R: Read> Reader<R> {
+	/// Create a capture reader, checking the header.
+	pub fn new(mut stream: R) -> Res<Reader<R>> {
+		let mut magic = [0u8; 6];
+		try!(stream.read_exact(&mut magic));
+
+		if &magic[..] != MAGIC || try!(stream.read_u8()) != VERSION {
+			return Err(Error::NotSupported);
+		}
+
+		Ok(Reader {
+			stream: stream,
+		})
+	}
+
+	/// Read the next frame, `None` when the capture is over.
+	///
+	/// A capture ending in the middle of a frame is an error.
+	pub fn frame(&mut self) -> Res<Option<Frame>> {
+		let mut header = [0u8; 10];
+		let mut read   = 0;
+
+		while read < header.len() {
+			match self.stream.read(&mut header[read ..]) {
+				Ok(0) =>
+					break,
+
+				Ok(size) =>
+					read += size,
+
+				Err(ref err) if err.kind() == io::ErrorKind::Interrupted =>
+					continue,
+
+				Err(err) =>
+					return Err(err.into()),
+			}
+		}
+
+		if read == 0 {
+			return Ok(None);
+		}
+
+		if read < header.len() {
+			return Err(truncated());
+		}
+
+		let at   = (&header[.. 8]).read_u64::<LittleEndian>().unwrap();
+		let at   = Duration::new(at / 1_000_000, ((at % 1_000_000) * 1_000) as u32);
+		let id   = header[8];
+		let size = header[9];
+
+		let mut payload = vec![0u8; size as usize];
+		try!(self.stream.read_exact(&mut payload).map_err(|err|
+			if err.kind() == io::ErrorKind::UnexpectedEof { truncated() } else { err.into() }));
+
+		Ok(Some(Frame {
+			at:      at,
+			id:      id,
+			payload: payload,
+		}))
+	}
+}
+
+fn truncated() -> Error {
+	io::Error::new(io::ErrorKind::InvalidData, "truncated frame").into()
+}
+
+impl<R: Read> Iterator for Reader<R> {
+	type Item = Res<Frame>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		match self.frame() {
+			Ok(Some(frame)) =>
+				Some(Ok(frame)),
+
+			Ok(None) =>
+				None,
+
+			Err(err) =>
+				Some(Err(err)),
+		}
+	}
+}
+
+/// Transport replaying a capture.
+///
+/// Feature reports are accepted and ignored, interrupt reads return the
+/// captured frames at the original pace scaled by the speed.
+pub struct Replay<R: Read> {
+	reader: Reader<R>,
+	speed:  f64,
+	start:  Option<Instant>,
+}
+
+impl<R: Read> Replay<R> {
+	/// Replay the capture from the given reader.
+	pub fn new(reader: Reader<R>) -> Replay<R> {
+		Replay {
+			reader: reader,
+			speed:  1.0,
+			start:  None,
+		}
+	}
+
+	/// The speed multiplier, `0.0` replays without waiting.
+	pub fn speed(mut self, value: f64) -> Self {
+		self.speed = value;
+		self
+	}
+}
+
+impl<R: Read> Transport for Replay<R> {
+	fn send_feature(&mut self, _packet: &[u8]) -> Res<()> {
+		Ok(())
+	}
+
+	fn get_feature(&mut self, _packet: &mut [u8]) -> Res<()> {
+		Err(Error::NotSupported)
+	}
+
+	fn read_interrupt(&mut self, packet: &mut [u8], _timeout: Duration) -> Res<usize> {
+		let frame = match try!(self.reader.frame()) {
+			Some(frame) =>
+				frame,
+
+			None =>
+				return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "capture is over").into()),
+		};
+
+		let start = *self.start.get_or_insert_with(Instant::now);
+
+		if self.speed > 0.0 {
+			let at      = micros(frame.at) as f64 / self.speed;
+			let at      = Duration::new((at / 1_000_000.0) as u64, ((at % 1_000_000.0) * 1_000.0) as u32);
+			let elapsed = start.elapsed();
+
+			if at > elapsed {
+				thread::sleep(at - elapsed);
+			}
+		}
+
+		for byte in packet.iter_mut() {
+			*byte = 0;
+		}
+
+		packet[0] = 0x01;
+		packet[2] = frame.id;
+		packet[3] = frame.payload.len() as u8;
+		packet[4 .. frame.payload.len() + 4].clone_from_slice(&frame.payload);
+
+		Ok(packet.len())
+	}
+}
+
+/// Create a capture file at the given path.
+pub fn create<P: AsRef<Path>>(path: P) -> Res<Writer<File>> {
+	Writer::new(try!(File::create(path)))
+}
+
+/// Open a capture file at the given path.
+pub fn open<P: AsRef<Path>>(path: P) -> Res<Reader<File>> {
+	Reader::new(try!(File::open(path)))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::Cursor;
+	use std::time::Duration;
+	use {Result as Res, Controller, State, Battery};
+	use super::{Writer, Reader, Replay, Frame};
+
+	#[test]
+	fn roundtrip() {
+		let mut writer = Writer::new(Vec::new()).unwrap();
+		writer.frame(&Frame { at: Duration::from_millis(4), id: 0x04, payload: vec![0x2a, 0, 0, 0] }).unwrap();
+		writer.frame(&Frame { at: Duration::from_millis(8), id: 0x03, payload: vec![0x01] }).unwrap();
+
+		let mut stream = writer.stream;
+		let     frames = Reader::new(Cursor::new(&stream[..])).unwrap().collect::<Res<Vec<_>>>().unwrap();
+		assert_eq!(2, frames.len());
+		assert_eq!(Duration::from_millis(8), frames[1].at);
+		assert_eq!(vec![0x01], frames[1].payload);
+
+		// A truncated capture is an error, not the end of it.
+		stream.pop();
+		let frames = Reader::new(Cursor::new(&stream[..])).unwrap().collect::<Vec<_>>();
+		assert_eq!(2, frames.len());
+		assert!(frames[0].is_ok());
+		assert!(frames[1].is_err());
+
+		stream.truncate(stream.len() - 8);
+		assert!(Reader::new(Cursor::new(&stream[..])).unwrap().nth(1).unwrap().is_err());
+	}
+
+	#[test]
+	fn replay() {
+		let mut writer = Writer::new(Vec::new()).unwrap();
+		writer.write(0x04, &[0x2a, 0, 0, 0, 0, 0, 0, 0, 0xe8, 0x0e, 0x50]).unwrap();
+		writer.write(0x03, &[0x01]).unwrap();
+
+		let replay         = Replay::new(Reader::new(Cursor::new(writer.stream)).unwrap()).speed(0.0);
+		let mut controller = Controller::new(replay, 0x1102).unwrap();
+
+		assert_eq!(State::Idle { sequence: 42, battery: Battery { voltage: 3816, percent: 80 } }, controller.state(Duration::from_secs(0)).unwrap());
+		assert_eq!(State::Power(false), controller.state(Duration::from_secs(0)).unwrap());
+		assert!(controller.state(Duration::from_secs(0)).is_err());
+	}
+}
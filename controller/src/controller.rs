@@ -1,121 +1,75 @@
-#[cfg(not(target_os = "linux"))]
-use std::marker::PhantomData;
-
 use std::time::Duration;
+use std::cmp;
 use std::thread;
 use std::io::{self, Cursor, Write};
-use byteorder::{WriteBytesExt, LittleEndian};
-
-#[cfg(target_os = "linux")]
-use usb;
-
-#[cfg(not(target_os = "linux"))]
-use hid;
-
-use {Result as Res, Error, State, Details};
-use {Lizard, Feedback, Sensors, Led, Sound, Calibrate, details};
+use byteorder::WriteBytesExt;
 
-const LIMIT:    u64 = 10;
-const INCREASE: u64 = 50;
+use {Result as Res, Error, State, Battery, Details, Transport, Events, Policy, Metrics};
+use {Lizard, Feedback, Sensors, Led, Sound, Calibrate, Settings, details};
+use settings::Shadow;
+use error;
 
 macro_rules! request {
-	($limit:ident, $body:expr) => (
+	($this:ident, $id:expr, $retry:ident, $body:expr) => (
 		match $body {
 			Ok(v) => {
 				v
 			}
 
 			Err(e) => {
-				if $limit == 0 {
-					try!(Err(e));
+				// Retrying can't help a controller that's gone.
+				if $retry >= $this.policy.get_retries() || e.is_disconnected() {
+					$this.metrics.failures += 1;
+					return Err(error::request(e, $id));
 				}
 
-				thread::sleep(Duration::from_millis((LIMIT - $limit) * INCREASE));
+				thread::sleep($this.policy.delay($retry));
 
-				$limit -= 1;
+				$retry += 1;
+				$this.metrics.retries += 1;
+				$this.metrics.most     = cmp::max($this.metrics.most, $retry);
 				continue;
 			}
 		}
 	)
 }
 
-#[doc(hidden)]
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-pub struct Settings {
-	pub timeout: u16,
-	pub sensors: bool,
-	pub lizard:  bool,
-}
-
-impl Default for Settings {
-	fn default() -> Self {
-		Settings {
-			timeout: 360,
-			sensors: false,
-			lizard:  false,
-		}
-	}
-}
-
 /// The controller.
-#[cfg(target_os = "linux")]
-pub struct Controller<'a> {
-	handle:   usb::DeviceHandle<'a>,
-	packet:   [u8; 64],
-	settings: Settings,
+pub struct Controller<T: Transport> {
+	transport: T,
+	packet:    [u8; 64],
+	shadow:    Shadow,
+	battery:   Option<Battery>,
 
+	restore: bool,
 	product: u16,
-	address: u8,
-	index:   u16,
-}
-
-#[cfg(not(target_os = "linux"))]
-pub struct Controller<'a> {
-	handle:  hid::Handle,
-	packet:  [u8; 65],
-	settings: Settings,
 
-	product: u16,
-	marker:  PhantomData<&'a ()>,
+	policy:  Policy,
+	metrics: Metrics,
 }
 
-impl<'a> Controller<'a> {
-	#[doc(hidden)]
-	#[cfg(target_os = "linux")]
-	pub fn new<'b>(mut device: usb::Device<'b>, mut handle: usb::DeviceHandle<'b>, product: u16, endpoint: u8, index: u16) -> Res<Controller<'b>> {
-		let mut address: Option<u8> = None;
-
-		for i in 0 .. try!(device.device_descriptor()).num_configurations() {
-			for interface in try!(device.config_descriptor(i)).interfaces() {
-				if try!(handle.kernel_driver_active(interface.number())) {
-					try!(handle.detach_kernel_driver(interface.number()));
-				}
+impl<T: Transport> Controller<T> {
+	/// Create a controller talking over the given transport.
+	pub fn new(transport: T, product: u16) -> Res<Controller<T>> {
+		Controller::with_policy(transport, product, Policy::default())
+	}
 
-				for descriptor in interface.descriptors() {
-					if descriptor.class_code() == 3 &&
-					   descriptor.sub_class_code() == 0 &&
-					   descriptor.protocol_code() == 0
-					{
-						try!(handle.claim_interface(descriptor.interface_number()));
-					}
-
-					for end in descriptor.endpoint_descriptors() {
-						if end.number() == endpoint {
-							address = Some(end.address());
-						}
-					}
-				}
-			}
-		}
+	/// Create a controller talking over the given transport, the policy is
+	/// already used by the requests sent while opening it.
+	pub fn with_policy(mut transport: T, product: u16, policy: Policy) -> Res<Controller<T>> {
+		transport.timeout(policy.get_timeout());
 
 		let mut controller = Controller {
-			handle:   handle,
-			packet:   [0u8; 64],
-			settings: Default::default(),
+			transport: transport,
+			packet:    [0u8; 64],
+			shadow:    Default::default(),
+			battery:   None,
 
+			restore: true,
 			product: product,
-			address: try!(address.ok_or(usb::Error::InvalidParam)),
-			index:   index,
+
+			policy:  policy,
+			metrics: Default::default(),
 		};
 
 		try!(controller.reset());
@@ -123,21 +77,14 @@ impl<'a> Controller<'a> {
 		Ok(controller)
 	}
 
-	#[cfg(not(target_os = "linux"))]
-	pub fn new<'b>(handle: hid::Handle, product: u16) -> Res<Controller<'b>> {
-		let mut controller = Controller {
-			handle:   handle,
-			packet:   [0u8; 65],
-			settings: Default::default(),
-
-			product: product,
-
-			marker: PhantomData,
-		};
-
-		try!(controller.reset());
+	/// Get the underlying transport.
+	pub fn transport(&self) -> &T {
+		&self.transport
+	}
 
-		Ok(controller)
+	/// Get the underlying transport mutably.
+	pub fn transport_mut(&mut self) -> &mut T {
+		&mut self.transport
 	}
 
 	/// Check if the controller is remote.
@@ -164,42 +111,45 @@ impl<'a> Controller<'a> {
 		}
 	}
 
+	/// Set whether lizard mode is enabled and the kernel drivers are
+	/// reattached when the controller is dropped, it's enabled by default.
+	pub fn restore(&mut self, value: bool) {
+		self.restore = value;
+		self.transport.restore(value);
+	}
+
+	/// Set how requests are retried.
+	pub fn policy(&mut self, value: Policy) {
+		self.transport.timeout(value.get_timeout());
+		self.policy = value;
+	}
+
+	/// Get the request statistics.
+	pub fn metrics(&self) -> Metrics {
+		self.metrics
+	}
+
+	/// Clear the request statistics.
+	pub fn clear_metrics(&mut self) {
+		self.metrics = Default::default();
+	}
+
 	#[doc(hidden)]
-	pub fn settings(&mut self) -> &mut Settings {
-		&mut self.settings
+	pub fn shadow(&mut self) -> &mut Shadow {
+		&mut self.shadow
 	}
 
 	#[doc(hidden)]
 	pub fn reset(&mut self) -> Res<()> {
-		let timeout = self.settings.timeout;
-
-		if self.settings.lizard {
+		if self.shadow.lizard {
 			try!(self.control(0x85));
 		}
 		else {
 			try!(self.control(0x81));
 		}
 
-		if self.settings.sensors {
-			try!(self.control_with(0x87, 0x15, |mut buf| {
-				try!(buf.write_u8(0x32));
-				try!(buf.write_u16::<LittleEndian>(timeout));
-				try!(buf.write(&[0x18, 0x00, 0x00, 0x31, 0x02, 0x00, 0x08, 0x07, 0x00, 0x07, 0x07, 0x00, 0x30]));
-				try!(buf.write_u8(0x14));
-				buf.write(&[0x00, 0x2e])
-			}));
-		}
-		else {
-			try!(self.control_with(0x87, 0x15, |mut buf| {
-				try!(buf.write_u8(0x32));
-				try!(buf.write_u16::<LittleEndian>(timeout));
-				try!(buf.write(&[0x18, 0x00, 0x00, 0x31, 0x02, 0x00, 0x08, 0x07, 0x00, 0x07, 0x07, 0x00, 0x30]));
-				try!(buf.write_u8(0x00));
-				buf.write(&[0x00, 0x2e])
-			}));
-		}
-
-		Ok(())
+		let values = self.shadow.values().to_vec();
+		Settings::write(self, &values)
 	}
 
 	#[doc(hidden)]
@@ -208,31 +158,15 @@ impl<'a> Controller<'a> {
 	}
 
 	#[doc(hidden)]
-	#[cfg(target_os = "linux")]
-	pub fn control_with<T, F>(&mut self, id: u8, size: u8, func: F) -> Res<()>
-		where F: FnOnce(Cursor<&mut [u8]>) -> io::Result<T>
+	pub fn control_with<R, F>(&mut self, id: u8, size: u8, func: F) -> Res<()>
+		where F: FnOnce(Cursor<&mut [u8]>) -> io::Result<R>
 	{
 		self.packet.clone_from_slice(&[0; 64][..]);
 		self.packet[0] = id;
 		self.packet[1] = size;
 
 		try!(func(Cursor::new(&mut self.packet[2..])));
-		try!(self.handle.write_control(0x21, 0x09, 0x0300, self.index, &self.packet[..], Duration::from_secs(0)));
-
-		Ok(())
-	}
-
-	#[doc(hidden)]
-	#[cfg(not(target_os = "linux"))]
-	pub fn control_with<T, F>(&mut self, func: F) -> Res<()>
-		where F: FnOnce(Cursor<&mut [u8]>) -> io::Result<T>
-	{
-		self.packet.clone_from_slice(&[0; 65][..]);
-		self.packet[1] = id;
-		self.packet[2] = size;
-
-		try!(func(Cursor::new(&mut self.packet[3..])));
-		try!(self.handle.feature().send(&self.packet[..]));
+		try!(self.transport.send_feature(&self.packet[..]).map_err(|e| error::request(e, id)));
 
 		Ok(())
 	}
@@ -243,9 +177,8 @@ impl<'a> Controller<'a> {
 	}
 
 	#[doc(hidden)]
-	#[cfg(target_os = "linux")]
-	pub fn request_with<T, F>(&mut self, id: u8, size: u8, func: F) -> Res<&[u8]>
-		where F: FnOnce(Cursor<&mut [u8]>) -> io::Result<T>
+	pub fn request_with<R, F>(&mut self, id: u8, size: u8, func: F) -> Res<&[u8]>
+		where F: FnOnce(Cursor<&mut [u8]>) -> io::Result<R>
 	{
 		self.packet.clone_from_slice(&[0; 64][..]);
 		self.packet[0] = id;
@@ -253,84 +186,61 @@ impl<'a> Controller<'a> {
 
 		try!(func(Cursor::new(&mut self.packet[2..])));
 
-		let mut limit = LIMIT;
-		loop {
-			request!(limit, self.handle.write_control(0x21, 0x09, 0x0300, self.index,
-				&self.packet[..], Duration::from_secs(0)));
+		self.metrics.requests += 1;
 
-			request!(limit, self.handle.read_control(0xa1, 0x01, 0x0300,
-				self.index, &mut self.packet[..], Duration::from_secs(0)));
+		let mut retry = 0;
+		loop {
+			request!(self, id, retry, self.transport.send_feature(&self.packet[..]));
+			request!(self, id, retry, self.transport.get_feature(&mut self.packet[..]));
 
 			if self.packet[0] == id && self.packet[1] != 0 {
 				break;
 			}
 
-			request!(limit, Err(Error::NotSupported));
+			request!(self, id, retry, Err(Error::NotSupported));
 		}
 
 		Ok(&self.packet[2 .. (self.packet[1] + 2) as usize])
 	}
 
-	#[doc(hidden)]
-	#[cfg(not(target_os = "linux"))]
-	pub fn request_with<T, F>(&mut self, id: u8, size: u8, func: F) -> Res<&[u8]>
-		where F: FnOnce(Cursor<&mut [u8]>) -> io::Result<T>
-	{
-		self.packet.clone_from_slice(&[0; 65][..]);
-		self.packet[1] = id;
-		self.packet[2] = size;
-
-		try!(func(Cursor::new(&mut self.packet[3..])));
-
-		let mut limit = LIMIT;
-		loop {
-			request!(limit, self.handle.feature().send(&self.packet[..]));
-			request!(limit, self.handle.feature().get(&mut self.packet[..]));
-
-			if self.packet[1] == id && self.packet[2] != 0 {
-				break;
-			}
-
-			request!(limit, Err(Error::NotSupported));
-		}
-
-		Ok(&self.packet[3 .. (self.packet[2] + 2) as usize])
-	}
-
 	/// Get the lizard manager.
-	pub fn lizard<'b>(&'b mut self) -> Lizard<'b, 'a> where 'a: 'b {
+	pub fn lizard<'a>(&'a mut self) -> Lizard<'a, T> {
 		Lizard::new(self)
 	}
 
 	/// Get the led manager.
-	pub fn led<'b>(&'b mut self) -> Led<'b, 'a> where 'a: 'b {
+	pub fn led<'a>(&'a mut self) -> Led<'a, T> {
 		Led::new(self)
 	}
 
 	/// Get the feedback builder.
-	pub fn feedback<'b>(&'b mut self) -> Feedback<'b, 'a> where 'a: 'b {
+	pub fn feedback<'a>(&'a mut self) -> Feedback<'a, T> {
 		Feedback::new(self)
 	}
 
 	/// Get the sensor manager.
-	pub fn sensors<'b>(&'b mut self) -> Sensors<'b, 'a> where 'a: 'b {
+	pub fn sensors<'a>(&'a mut self) -> Sensors<'a, T> {
 		Sensors::new(self)
 	}
 
 	/// Get the calibration manager.
-	pub fn calibrate<'b>(&'b mut self) -> Calibrate<'b, 'a> where 'a: 'b {
+	pub fn calibrate<'a>(&'a mut self) -> Calibrate<'a, T> {
 		Calibrate::new(self)
 	}
 
+	/// Get the settings manager.
+	pub fn settings<'a>(&'a mut self) -> Settings<'a, T> {
+		Settings::new(self)
+	}
+
 	/// Get the sound player.
-	pub fn sound<'b>(&'b mut self) -> Sound<'b, 'a> where 'a: 'b {
+	pub fn sound<'a>(&'a mut self) -> Sound<'a, T> {
 		Sound::new(self)
 	}
 
 	/// Set the idle duration before turning off.
 	pub fn timeout(&mut self, value: Duration) -> Res<()> {
-		self.settings.timeout = value.as_secs() as u16;
-		self.reset()
+		self.settings().timeout(value)
 	}
 
 	/// Turn the controller off.
@@ -373,24 +283,24 @@ impl<'a> Controller<'a> {
 		})
 	}
 
-	#[doc(hidden)]
-	#[cfg(target_os = "linux")]
-	pub fn receive(&mut self, timeout: Duration) -> Res<(u8, &[u8])> {
-		if try!(self.handle.read_interrupt(self.address, &mut self.packet, timeout)) != 64 {
-			return Err(Error::InvalidParameter);
-		}
+	/// Get the last battery status reported, it's sent along with idle states.
+	pub fn battery(&self) -> Option<Battery> {
+		self.battery
+	}
 
-		Ok((self.packet[2], &self.packet[4 .. (self.packet[3] + 4) as usize]))
+	/// Iterate over the incoming states.
+	pub fn events<'a>(&'a mut self) -> Events<'a, T> {
+		Events::new(self)
 	}
 
 	#[doc(hidden)]
-	#[cfg(not(target_os = "linux"))]
 	pub fn receive(&mut self, timeout: Duration) -> Res<(u8, &[u8])> {
-		if try!(self.handle.data().read(&mut self.packet[1..], timeout)).unwrap_or(0) != 64 {
+		if try!(self.transport.read_interrupt(&mut self.packet, timeout)) != 64 {
 			return Err(Error::InvalidParameter);
 		}
 
-		Ok((self.packet[3], &self.packet[5 .. (self.packet[4] + 5) as usize]))
+		let size = cmp::min(self.packet[3] as usize, 60);
+		Ok((self.packet[2], &self.packet[4 .. size + 4]))
 	}
 
 	/// Get the current state of the controller.
@@ -400,10 +310,100 @@ impl<'a> Controller<'a> {
 			try!(State::parse(id, Cursor::new(buffer)))
 		};
 
-		if let State::Power(true) = state {
-			try!(self.reset());
+		match state {
+			State::Power(true) =>
+				try!(self.reset()),
+
+			State::Power(false) =>
+				self.battery = None,
+
+			State::Idle { battery, .. } =>
+				self.battery = Some(battery),
+
+			_ =>
+				()
 		}
 
 		Ok(state)
 	}
 }
+
+impl<T: Transport> Drop for Controller<T> {
+	fn drop(&mut self) {
+		// Loading the defaults also resets the settings.
+		if self.restore && !self.shadow.lizard {
+			let _ = self.control(0x85);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::rc::Rc;
+	use std::cell::RefCell;
+	use std::time::Duration;
+	use transport::Mock;
+	use {Result as Res, Controller, Transport, State, Battery};
+
+	struct Shared(Rc<RefCell<Mock>>);
+
+	impl Transport for Shared {
+		fn send_feature(&mut self, packet: &[u8]) -> Res<()> {
+			self.0.borrow_mut().send_feature(packet)
+		}
+
+		fn get_feature(&mut self, packet: &mut [u8]) -> Res<()> {
+			self.0.borrow_mut().get_feature(packet)
+		}
+
+		fn read_interrupt(&mut self, packet: &mut [u8], timeout: Duration) -> Res<usize> {
+			self.0.borrow_mut().read_interrupt(packet, timeout)
+		}
+	}
+
+	#[test]
+	fn reset() {
+		let controller = Controller::new(Mock::new(), 0x1102).unwrap();
+		let sent       = controller.transport().sent();
+
+		assert_eq!(2, sent.len());
+		assert_eq!(&[0x81, 0x00][..], &sent[0][..2]);
+		assert_eq!(&[0x87, 0x15, 0x32, 0x68, 0x01][..], &sent[1][..5]);
+	}
+
+	#[test]
+	fn request() {
+		let mut mock = Mock::new();
+		mock.reply(0xb4, &[0x02]);
+
+		let mut controller = Controller::new(mock, 0x1142).unwrap();
+		assert!(controller.is_connected());
+		assert_eq!(&[0xb4, 0x00][..], &controller.transport().sent()[2][..2]);
+	}
+
+	#[test]
+	fn state() {
+		let mut mock = Mock::new();
+		mock.input(0x04, &[0x2a, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xe8, 0x0e, 0x50]);
+
+		let mut controller = Controller::new(mock, 0x1102).unwrap();
+		assert_eq!(State::Idle { sequence: 42, battery: Battery { voltage: 3816, percent: 80 } }, controller.state(Duration::from_secs(0)).unwrap());
+		assert_eq!(Some(Battery { voltage: 3816, percent: 80 }), controller.battery());
+		assert!(controller.state(Duration::from_secs(0)).is_err());
+	}
+
+	#[test]
+	fn restore() {
+		let mock = Rc::new(RefCell::new(Mock::new()));
+
+		drop(Controller::new(Shared(mock.clone()), 0x1102).unwrap());
+		assert_eq!(&[0x85, 0x00][..], &mock.borrow().sent()[2][..2]);
+
+		mock.borrow_mut().clear();
+
+		let mut controller = Controller::new(Shared(mock.clone()), 0x1102).unwrap();
+		controller.restore(false);
+		drop(controller);
+		assert_eq!(2, mock.borrow().sent().len());
+	}
+}
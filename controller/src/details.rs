@@ -1,85 +1,225 @@
+use std::fmt;
 use std::io::{Read, Seek, SeekFrom};
 use std::time::{UNIX_EPOCH, Duration, SystemTime};
+use std::collections::BTreeMap;
 use byteorder::{ReadBytesExt, LittleEndian, BigEndian};
 use {Result as Res};
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub struct Details {
 	pub build:    Build,
 	pub receiver: Option<Receiver>,
 	pub serial:   Serial,
 }
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+impl fmt::Display for Details {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		try!(writeln!(f, "{:<20} {}", "Controller serial:", self.serial.controller));
+		try!(writeln!(f, "{:<20} {}", "Mainboard serial:", self.serial.mainboard));
+
+		if let Some(ref receiver) = self.receiver {
+			try!(writeln!(f, "{:<20} {}", "Receiver serial:", receiver.serial));
+			try!(writeln!(f, "{:<20} {}", "Receiver firmware:", Date(receiver.firmware)));
+		}
+
+		for (key, &value) in &self.build.attributes {
+			try!(write!(f, "{:<20} ", format!("{}:", key)));
+
+			if key.is_date() {
+				try!(writeln!(f, "{} ({})", Date(date(value)), value));
+			}
+			else {
+				try!(writeln!(f, "{} (0x{:08x})", value, value));
+			}
+		}
+
+		Ok(())
+	}
+}
+
+/// A key in the build details.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Key {
+	/// The unique ID of the device.
+	UniqueId,
+
+	/// The USB product ID.
+	ProductId,
+
+	/// The hardware ID.
+	HardwareId,
+
+	/// The controller firmware build date, which doubles as build number.
+	Firmware,
+
+	/// The radio firmware build date.
+	Radio,
+
+	/// The board revision.
+	Revision,
+
+	/// The bootloader build date.
+	Bootloader,
+
+	/// A key that isn't known yet.
+	Unknown(u8),
+}
+
+impl From<u8> for Key {
+	fn from(value: u8) -> Key {
+		match value {
+			0x00 => Key::UniqueId,
+			0x01 => Key::ProductId,
+			0x02 => Key::HardwareId,
+			0x04 => Key::Firmware,
+			0x05 => Key::Radio,
+			0x09 => Key::Revision,
+			0x0a => Key::Bootloader,
+			id   => Key::Unknown(id),
+		}
+	}
+}
+
+impl Key {
+	/// The key ID.
+	pub fn id(&self) -> u8 {
+		match self {
+			&Key::UniqueId    => 0x00,
+			&Key::ProductId   => 0x01,
+			&Key::HardwareId  => 0x02,
+			&Key::Firmware    => 0x04,
+			&Key::Radio       => 0x05,
+			&Key::Revision    => 0x09,
+			&Key::Bootloader  => 0x0a,
+			&Key::Unknown(id) => id,
+		}
+	}
+
+	/// Check if the value is a build date.
+	pub fn is_date(&self) -> bool {
+		match self {
+			&Key::Firmware | &Key::Radio | &Key::Bootloader => true,
+			_ => false,
+		}
+	}
+}
+
+impl fmt::Display for Key {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			&Key::UniqueId =>
+				f.write_str("Unique ID"),
+
+			&Key::ProductId =>
+				f.write_str("Product ID"),
+
+			&Key::HardwareId =>
+				f.write_str("Hardware ID"),
+
+			&Key::Firmware =>
+				f.write_str("Firmware"),
+
+			&Key::Radio =>
+				f.write_str("Radio firmware"),
+
+			&Key::Revision =>
+				f.write_str("Board revision"),
+
+			&Key::Bootloader =>
+				f.write_str("Bootloader"),
+
+			&Key::Unknown(id) =>
+				write!(f, "Key 0x{:02x}", id),
+		}
+	}
+}
+
+#[derive(Clone, PartialEq, Debug)]
 pub struct Build {
 	pub revision:   i32,
 	pub bootloader: SystemTime,
 	pub firmware:   SystemTime,
 	pub radio:      SystemTime,
+
+	/// Every key/value pair in the response.
+	pub attributes: BTreeMap<Key, u32>,
 }
 
 impl Build {
 	pub fn parse<R: Read + Seek>(mut buffer: R) -> Res<Build> {
-		let mut revision   = 0;
-		let mut bootloader = 0;
-		let mut firmware   = 0;
-		let mut radio      = 0;
+		let mut attributes = BTreeMap::new();
 
 		while let Ok(key) = buffer.read_u8() {
-			match key {
-				0x09 => {
-					revision = try!(buffer.read_i32::<LittleEndian>());
-				}
-
-				0x0a => {
-					bootloader = try!(buffer.read_i32::<LittleEndian>());
-				}
+			let value = try!(buffer.read_u32::<LittleEndian>());
 
-				0x04 => {
-					firmware = try!(buffer.read_i32::<LittleEndian>());
-				}
+			// The response is padded with zeroes, a zero record followed by
+			// anything else is a genuine unique ID of zero.
+			if key == 0x00 && value == 0 {
+				let mut rest = Vec::new();
+				try!(buffer.read_to_end(&mut rest));
 
-				0x05 => {
-					radio = try!(buffer.read_i32::<LittleEndian>());
+				if rest.iter().all(|&b| b == 0) {
+					break;
 				}
 
-				_ => {
-					try!(buffer.seek(SeekFrom::Current(4)));
-				}
+				try!(buffer.seek(SeekFrom::Current(-(rest.len() as i64))));
 			}
+
+			attributes.insert(Key::from(key), value);
 		}
 
+		let (revision, bootloader, firmware, radio) = {
+			let get = |key| attributes.get(&key).cloned().unwrap_or(0);
+			(get(Key::Revision), get(Key::Bootloader), get(Key::Firmware), get(Key::Radio))
+		};
+
 		Ok(Build {
-			revision: revision,
+			revision: revision as i32,
 
-			bootloader: UNIX_EPOCH + Duration::from_secs(bootloader as u64),
-			firmware:   UNIX_EPOCH + Duration::from_secs(firmware as u64),
-			radio:      UNIX_EPOCH + Duration::from_secs(radio as u64),
+			bootloader: date(bootloader),
+			firmware:   date(firmware),
+			radio:      date(radio),
+
+			attributes: attributes,
 		})
 	}
+
+	/// Get the value for the given key.
+	pub fn get(&self, key: Key) -> Option<u32> {
+		self.attributes.get(&key).cloned()
+	}
+
+	/// Check if the firmware is at least the given build, an unknown build
+	/// isn't.
+	///
+	/// No build is known to change the protocol, so nothing checks it here;
+	/// it's for callers relying on newer firmware features.
+	pub fn is_at_least(&self, build: u32) -> bool {
+		self.get(Key::Firmware).map_or(false, |v| v >= build)
+	}
 }
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Serial {
-	pub mainboard:  [u8; 10],
-	pub controller: [u8; 10],
+	pub mainboard:  String,
+	pub controller: String,
 }
 
 impl Serial {
-	pub fn parse<R: Read>(mut buffer: R) -> Res<[u8; 10]> {
+	pub fn parse<R: Read>(mut buffer: R) -> Res<String> {
 		try!(buffer.read_u8());
 
 		let mut serial = [0u8; 10];
 		try!(buffer.read(&mut serial[..]));
 
-		Ok(serial)
+		Ok(string(&serial))
 	}
 }
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub struct Receiver {
 	pub firmware: SystemTime,
-	pub serial:   [u8; 10],
+	pub serial:   String,
 }
 
 impl Receiver {
@@ -92,7 +232,82 @@ impl Receiver {
 
 		Ok(Receiver {
 			firmware: UNIX_EPOCH + Duration::from_secs(firmware as u64),
-			serial:   serial,
+			serial:   string(&serial),
 		})
 	}
 }
+
+/// Serial numbers are ASCII padded with zeroes.
+fn string(value: &[u8]) -> String {
+	let end = value.iter().position(|&b| b == 0).unwrap_or(value.len());
+	String::from_utf8_lossy(&value[.. end]).into_owned()
+}
+
+fn date(value: u32) -> SystemTime {
+	UNIX_EPOCH + Duration::from_secs(value as u64)
+}
+
+/// Formats a time as an UTC date.
+struct Date(SystemTime);
+
+impl fmt::Display for Date {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let secs = self.0.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+		let time = secs % 86400;
+
+		// Days to civil date, from http://howardhinnant.github.io/date_algorithms.html
+		let z   = (secs / 86400) as i64 + 719468;
+		let era = z / 146097;
+		let doe = z - era * 146097;
+		let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+		let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+		let mp  = (5 * doy + 2) / 153;
+		let day = doy - (153 * mp + 2) / 5 + 1;
+		let mon = if mp < 10 { mp + 3 } else { mp - 9 };
+		let yea = yoe + era * 400 + if mon <= 2 { 1 } else { 0 };
+
+		write!(f, "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+			yea, mon, day, time / 3600, time % 3600 / 60, time % 60)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::Cursor;
+	use super::{Details, Build, Serial, Key};
+
+	#[test]
+	fn build() {
+		let build = Build::parse(Cursor::new(&[
+			0x00, 0x00, 0x00, 0x00, 0x00,
+			0x01, 0x02, 0x11, 0x00, 0x00,
+			0x09, 0x07, 0x00, 0x00, 0x00,
+			0x04, 0x80, 0x54, 0x03, 0x57,
+			0x0b, 0x2a, 0x00, 0x00, 0x00,
+			0x00, 0x00, 0x00, 0x00, 0x00,
+			0x00, 0x00, 0x00,
+		][..])).unwrap();
+
+		assert_eq!(7, build.revision);
+		assert_eq!(Some(0x1102), build.get(Key::ProductId));
+		assert_eq!(Some(42), build.get(Key::Unknown(0x0b)));
+		assert_eq!(Some(0), build.get(Key::UniqueId));
+		assert_eq!(5, build.attributes.len());
+		assert!(build.is_at_least(1459836032));
+		assert!(!build.is_at_least(1459836033));
+
+		let details = Details {
+			build:    build,
+			receiver: None,
+			serial:   Serial {
+				mainboard:  Serial::parse(Cursor::new(&b"\x00FXAA1234\x00"[..])).unwrap(),
+				controller: "FXBB5678".into(),
+			},
+		};
+
+		let output = details.to_string();
+		assert!(output.contains("Mainboard serial:    FXAA1234\n"));
+		assert!(output.contains("Firmware:            2016-04-05 06:00:32 UTC (1459836032)\n"));
+		assert!(output.contains("Key 0x0b:            42 (0x0000002a)\n"));
+	}
+}
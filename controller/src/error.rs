@@ -22,14 +22,133 @@ pub enum Error {
 
 	/// Not supported.
 	NotSupported,
+
+	/// No controller could be found.
+	NotFound,
+
+	/// The controller has been disconnected.
+	Disconnected,
+
+	/// The controller can't be accessed, usually because the udev rules are
+	/// missing.
+	PermissionDenied,
+
+	/// The controller is in bootloader mode.
+	Bootloader,
+
+	/// A report could not be parsed.
+	Parse {
+		/// The report ID.
+		id: u8,
+
+		/// The offset in the payload where parsing stopped.
+		offset: u64,
+	},
+
+	/// A request to the controller failed.
+	Request {
+		/// The request ID.
+		id: u8,
+
+		/// What went wrong.
+		cause: Box<Error>,
+	},
+}
+
+impl Error {
+	/// Check if the error is a timeout.
+	#[cfg(target_os = "linux")]
+	pub fn is_timeout(&self) -> bool {
+		match self {
+			&Error::Usb(usb::Error::Timeout) =>
+				true,
+
+			&Error::Io(ref err) =>
+				err.kind() == io::ErrorKind::TimedOut,
+
+			&Error::Request { ref cause, .. } =>
+				cause.is_timeout(),
+
+			_ =>
+				false
+		}
+	}
+
+	#[cfg(not(target_os = "linux"))]
+	pub fn is_timeout(&self) -> bool {
+		match self {
+			&Error::Io(ref err) =>
+				err.kind() == io::ErrorKind::TimedOut,
+
+			&Error::Request { ref cause, .. } =>
+				cause.is_timeout(),
+
+			_ =>
+				false
+		}
+	}
+
+	/// Check if the controller has been disconnected.
+	pub fn is_disconnected(&self) -> bool {
+		match self {
+			&Error::Disconnected =>
+				true,
+
+			&Error::Request { ref cause, .. } =>
+				cause.is_disconnected(),
+
+			_ =>
+				false
+		}
+	}
+}
+
+/// Add the request ID to the error.
+pub fn request(error: Error, id: u8) -> Error {
+	match error {
+		err @ Error::Request { .. } =>
+			err,
+
+		err =>
+			Error::Request { id: id, cause: Box::new(err) }
+	}
 }
 
 impl From<io::Error> for Error {
 	fn from(value: io::Error) -> Self {
-		Error::Io(value)
+		match value.kind() {
+			io::ErrorKind::PermissionDenied =>
+				Error::PermissionDenied,
+
+			io::ErrorKind::NotConnected | io::ErrorKind::BrokenPipe =>
+				Error::Disconnected,
+
+			_ =>
+				Error::Io(value)
+		}
 	}
 }
 
+#[cfg(target_os = "linux")]
+impl From<usb::Error> for Error {
+	fn from(value: usb::Error) -> Self {
+		match value {
+			usb::Error::Access =>
+				Error::PermissionDenied,
+
+			usb::Error::NoDevice =>
+				Error::Disconnected,
+
+			value =>
+				Error::Usb(value)
+		}
+	}
+}
+
+/// The hidapi errors don't tell what went wrong, so disconnections and
+/// permission errors are told apart by the transport and the manager, which
+/// know what was being done.
+#[cfg(not(target_os = "linux"))]
 impl From<usb::Error> for Error {
 	fn from(value: usb::Error) -> Self {
 		Error::Usb(value)
@@ -38,24 +157,88 @@ impl From<usb::Error> for Error {
 
 impl fmt::Display for Error {
 	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-		f.write_str(error::Error::description(self))
+		match self {
+			&Error::Io(ref err) =>
+				write!(f, "I/O error: {}", err),
+
+			&Error::Usb(ref err) =>
+				write!(f, "USB error: {}", err),
+
+			&Error::InvalidParameter =>
+				f.write_str("invalid parameter"),
+
+			&Error::NotSupported =>
+				f.write_str("the controller didn't answer the request, it may not support it"),
+
+			&Error::NotFound =>
+				f.write_str("no controller found, make sure it's plugged in or the wireless receiver is"),
+
+			&Error::Disconnected =>
+				f.write_str("the controller has been disconnected"),
+
+			#[cfg(target_os = "linux")]
+			&Error::PermissionDenied =>
+				f.write_str("permission denied, install the udev rules giving access to Valve devices, \
+					for example `SUBSYSTEM==\"usb\", ATTRS{idVendor}==\"28de\", MODE=\"0666\"`"),
+
+			#[cfg(target_os = "macos")]
+			&Error::PermissionDenied =>
+				f.write_str("permission denied, allow the program in System Preferences under \
+					Security & Privacy, Input Monitoring"),
+
+			#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+			&Error::PermissionDenied =>
+				f.write_str("permission denied, close any other program using the controller, like Steam"),
+
+			&Error::Bootloader =>
+				f.write_str("the controller is in bootloader mode, finish the firmware update through Steam"),
+
+			&Error::Parse { id, offset } =>
+				write!(f, "invalid report 0x{:02x} at byte {}", id, offset),
+
+			&Error::Request { id, ref cause } if cause.is_timeout() =>
+				write!(f, "request 0x{:02x} timed out", id),
+
+			&Error::Request { id, ref cause } =>
+				write!(f, "request 0x{:02x} failed: {}", id, cause),
+		}
 	}
 }
 
 impl error::Error for Error {
-	fn description(&self) -> &str {
+	fn source(&self) -> Option<&(error::Error + 'static)> {
 		match self {
 			&Error::Io(ref err) =>
-				err.description(),
+				Some(err),
 
 			&Error::Usb(ref err) =>
-				err.description(),
+				Some(err),
 
-			&Error::InvalidParameter =>
-				"Invalid parameter.",
+			&Error::Request { ref cause, .. } =>
+				Some(&**cause),
 
-			&Error::NotSupported =>
-				"Unsupported."
+			_ =>
+				None
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use std::error::Error as Std;
+	use std::io;
+	use super::{Error, request};
+
+	#[test]
+	fn context() {
+		let err = request(Error::from(io::Error::new(io::ErrorKind::TimedOut, "timeout")), 0x83);
+
+		assert!(err.is_timeout());
+		assert_eq!("request 0x83 timed out", err.to_string());
+		assert!(err.source().is_some());
+
+		let err = request(Error::from(io::Error::new(io::ErrorKind::PermissionDenied, "denied")), 0x83);
+		assert!(err.to_string().contains("udev"));
+		assert!(Error::Parse { id: 0x01, offset: 12 }.source().is_none());
+	}
+}
@@ -0,0 +1,308 @@
+use std::time::Duration;
+
+#[cfg(feature = "futures")]
+use std::thread;
+
+#[cfg(feature = "futures")]
+use std::sync::mpsc::{channel, Sender, Receiver};
+
+#[cfg(feature = "futures")]
+use futures::{Async, Poll, Stream};
+
+#[cfg(feature = "futures")]
+use futures::sync::mpsc;
+
+#[cfg(feature = "futures")]
+use {Error, Manager, Device, Policy};
+
+#[cfg(feature = "futures")]
+use haptic::Side;
+
+use {Result as Res, Controller, Transport, State};
+
+/// Iterator over the controller states.
+///
+/// The iteration ends when no state arrives within the timeout, or after an
+/// error has been returned; a zero timeout waits forever.
+pub struct Events<'a, T: Transport + 'a> {
+	controller: &'a mut Controller<T>,
+	timeout:    Duration,
+	done:       bool,
+}
+
+impl<'a, T: Transport> Events<'a, T> {
+	#[doc(hidden)]
+	pub fn new(controller: &'a mut Controller<T>) -> Events<'a, T> {
+		Events {
+			controller: controller,
+			timeout:    Duration::from_secs(0),
+			done:       false,
+		}
+	}
+
+	/// How long to wait for a state.
+	pub fn timeout(mut self, value: Duration) -> Self {
+		self.timeout = value;
+		self
+	}
+
+	/// Get the controller, to send commands between states.
+	pub fn controller(&mut self) -> &mut Controller<T> {
+		self.controller
+	}
+}
+
+impl<'a, T: Transport> Iterator for Events<'a, T> {
+	type Item = Res<State>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.done {
+			return None;
+		}
+
+		match self.controller.state(self.timeout) {
+			Ok(state) =>
+				Some(Ok(state)),
+
+			Err(ref err) if err.is_timeout() =>
+				None,
+
+			Err(err) => {
+				self.done = true;
+				Some(Err(err))
+			}
+		}
+	}
+}
+
+/// A command for the controller of a `Listener`.
+#[cfg(feature = "futures")]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Command {
+	/// Send haptic feedback on the given pads, see `Feedback`.
+	Feedback {
+		side:      Side,
+		amplitude: u16,
+		period:    u16,
+		count:     u16,
+	},
+
+	/// Change the led luminosity, see `Led`.
+	Led(u8),
+}
+
+/// Stream of the states of a controller, read on a dedicated thread so the
+/// executor is never blocked; commands are sent to the controller by the same
+/// thread between states.
+///
+/// The thread stops when the listener is dropped, or after an error has been
+/// returned.
+#[cfg(feature = "futures")]
+pub struct Listener {
+	rx:       mpsc::UnboundedReceiver<Res<State>>,
+	commands: Sender<Command>,
+}
+
+#[cfg(feature = "futures")]
+impl Listener {
+	/// Read the states of the given controller.
+	pub fn new<T: Transport + Send + 'static>(controller: Controller<T>) -> Listener {
+		let (tx, rx)             = mpsc::unbounded();
+		let (commands, receiver) = channel();
+		thread::spawn(move || read(controller, tx, receiver));
+
+		Listener {
+			rx:       rx,
+			commands: commands,
+		}
+	}
+
+	#[doc(hidden)]
+	pub fn open(device: Device, policy: Policy) -> Listener {
+		let (tx, rx)             = mpsc::unbounded();
+		let (commands, receiver) = channel();
+
+		// The controller borrows the manager, so both have to live on the thread.
+		thread::spawn(move || {
+			let manager = match Manager::new() {
+				Ok(manager) =>
+					manager.policy(policy),
+
+				Err(err) =>
+					return drop(tx.unbounded_send(Err(err))),
+			};
+
+			let controller = match manager.open_device(&device) {
+				Ok(controller) =>
+					controller,
+
+				Err(err) =>
+					return drop(tx.unbounded_send(Err(err))),
+			};
+
+			read(controller, tx, receiver);
+		});
+
+		Listener {
+			rx:       rx,
+			commands: commands,
+		}
+	}
+
+	/// Send a command to the controller, errors running it end the stream.
+	pub fn send(&self, command: Command) -> Res<()> {
+		self.commands.send(command).map_err(|_| Error::Disconnected)
+	}
+}
+
+#[cfg(feature = "futures")]
+fn command<T: Transport>(controller: &mut Controller<T>, command: Command) -> Res<()> {
+	match command {
+		Command::Feedback { side, amplitude, period, count } => {
+			for &pad in &[Side::Left, Side::Right] {
+				if side == pad || side == Side::Both {
+					let feedback = controller.feedback().amplitude(amplitude).period(period).count(count);
+					try!(if pad == Side::Left { feedback.left() } else { feedback.right() }.send());
+				}
+			}
+
+			Ok(())
+		}
+
+		Command::Led(value) =>
+			controller.led().level(value),
+	}
+}
+
+#[cfg(feature = "futures")]
+fn read<T: Transport>(mut controller: Controller<T>, tx: mpsc::UnboundedSender<Res<State>>, commands: Receiver<Command>) {
+	loop {
+		for value in commands.try_iter() {
+			if let Err(err) = command(&mut controller, value) {
+				let _ = tx.unbounded_send(Err(err));
+				return;
+			}
+		}
+
+		// Keep the timeout short so commands aren't delayed.
+		match controller.state(Duration::from_millis(10)) {
+			Ok(state) =>
+				if tx.unbounded_send(Ok(state)).is_err() {
+					break;
+				},
+
+			Err(ref err) if err.is_timeout() =>
+				if tx.is_closed() {
+					break;
+				},
+
+			Err(err) => {
+				let _ = tx.unbounded_send(Err(err));
+				break;
+			}
+		}
+	}
+}
+
+#[cfg(feature = "futures")]
+impl Stream for Listener {
+	type Item  = State;
+	type Error = Error;
+
+	fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+		match self.rx.poll() {
+			Ok(Async::Ready(Some(Ok(state)))) =>
+				Ok(Async::Ready(Some(state))),
+
+			Ok(Async::Ready(Some(Err(err)))) =>
+				Err(err),
+
+			Ok(Async::NotReady) =>
+				Ok(Async::NotReady),
+
+			Ok(Async::Ready(None)) | Err(()) =>
+				Ok(Async::Ready(None)),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+	use transport::Mock;
+	use {Controller, State, Battery};
+
+	#[test]
+	fn events() {
+		let mut mock = Mock::new();
+		mock.input(0x04, &[0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x64]);
+		mock.input(0x04, &[0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x64]);
+
+		let mut controller = Controller::new(mock, 0x1102).unwrap();
+		let     states     = controller.events().timeout(Duration::from_millis(1))
+			.collect::<Result<Vec<_>, _>>().unwrap();
+
+		let battery = Battery { voltage: 0, percent: 100 };
+		assert_eq!(vec![State::Idle { sequence: 1, battery: battery }, State::Idle { sequence: 2, battery: battery }], states);
+	}
+
+	#[cfg(feature = "futures")]
+	#[test]
+	fn listener() {
+		use futures::Stream;
+		use events::Listener;
+
+		let mut mock = Mock::new();
+		mock.input(0x04, &[0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x64]);
+		mock.input(0x03, &[0x01]);
+
+		let states = Listener::new(Controller::new(mock, 0x1102).unwrap()).take(2).wait()
+			.collect::<Result<Vec<_>, _>>().unwrap();
+
+		assert_eq!(vec![State::Idle { sequence: 1, battery: Battery { voltage: 0, percent: 100 } }, State::Power(false)], states);
+	}
+
+	#[cfg(feature = "futures")]
+	#[test]
+	fn command() {
+		use std::thread;
+		use std::sync::{Arc, Mutex};
+		use futures::Stream;
+		use events::{Listener, Command};
+		use {Result as Res, Transport};
+
+		struct Shared(Arc<Mutex<Mock>>);
+
+		impl Transport for Shared {
+			fn send_feature(&mut self, packet: &[u8]) -> Res<()> {
+				self.0.lock().unwrap().send_feature(packet)
+			}
+
+			fn get_feature(&mut self, packet: &mut [u8]) -> Res<()> {
+				self.0.lock().unwrap().get_feature(packet)
+			}
+
+			fn read_interrupt(&mut self, packet: &mut [u8], timeout: Duration) -> Res<usize> {
+				self.0.lock().unwrap().read_interrupt(packet, timeout)
+			}
+		}
+
+		let mock         = Arc::new(Mutex::new(Mock::new()));
+		let mut listener = Listener::new(Controller::new(Shared(mock.clone()), 0x1102).unwrap());
+		listener.send(Command::Led(50)).unwrap();
+
+		// The command is sent between reads.
+		for _ in 0 .. 100 {
+			if mock.lock().unwrap().sent().iter().any(|p| p[0] == 0x87 && p[2] == 0x2d && p[3] == 50) {
+				break;
+			}
+
+			thread::sleep(Duration::from_millis(10));
+		}
+
+		assert!(mock.lock().unwrap().sent().iter().any(|p| p[0] == 0x87 && p[2] == 0x2d && p[3] == 50));
+
+		listener.send(Command::Led(200)).unwrap();
+		assert!(listener.by_ref().wait().next().unwrap().is_err());
+	}
+}
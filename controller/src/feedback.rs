@@ -1,18 +1,18 @@
 use byteorder::{WriteBytesExt, LittleEndian};
-use {Result as Res, Controller};
+use {Result as Res, Controller, Transport};
 
 /// Controller feedback builder.
-pub struct Feedback<'a, 'b: 'a> {
-	controller: &'a mut Controller<'b>,
+pub struct Feedback<'a, T: Transport + 'a> {
+	controller: &'a mut Controller<T>,
 	side:       u8,
 	amplitude:  u16,
 	period:     u16,
 	count:      u16,
 }
 
-impl<'a, 'b> Feedback<'a, 'b> {
+impl<'a, T: Transport> Feedback<'a, T> {
 	#[doc(hidden)]
-	pub fn new(controller: &'a mut Controller<'b>) -> Feedback<'a, 'b> {
+	pub fn new(controller: &'a mut Controller<T>) -> Feedback<'a, T> {
 		Feedback {
 			controller: controller,
 			side:       0,
@@ -69,3 +69,19 @@ impl<'a, 'b> Feedback<'a, 'b> {
 		})
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use transport::Mock;
+	use Controller;
+
+	#[test]
+	fn send() {
+		let mut controller = Controller::new(Mock::new(), 0x1102).unwrap();
+		controller.transport_mut().clear();
+		controller.feedback().left().amplitude(0x0102).period(0x0304).count(2).send().unwrap();
+
+		assert_eq!(&[0x8f, 0x08, 0x01, 0x02, 0x01, 0x04, 0x03, 0x02, 0x00][..],
+			&controller.transport().sent()[0][..9]);
+	}
+}
@@ -0,0 +1,462 @@
+//! Haptic patterns on top of `Feedback`.
+//!
+//! A pattern is a timed sequence of pulses, the `Scheduler` plays many of
+//! them at once and has to be ticked regularly, it tells how long until the
+//! next pulse is due so it can be driven by the same loop reading states.
+//!
+//! Patterns can be loaded from text files, where each line is a pulse made of
+//! the offset in milliseconds, the side (`left`, `right` or `both`), the
+//! amplitude, the period and the count; everything after a `#` is ignored.
+//!
+//! ```text
+//! # double click on the right pad
+//! 0   right 1024 0 1
+//! 80  right 1024 0 1
+//! ```
+
+use std::io::{Read, BufRead, BufReader};
+use std::fs::File;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use {Result as Res, Error, Controller, Transport};
+
+/// The pads a pulse is sent to.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Side {
+	/// The left pad.
+	Left,
+
+	/// The right pad.
+	Right,
+
+	/// Both pads.
+	Both,
+}
+
+impl Side {
+	fn overlaps(&self, other: Side) -> bool {
+		*self == Side::Both || other == Side::Both || *self == other
+	}
+}
+
+/// A single pulse in a pattern.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Pulse {
+	/// Offset from the start of the pattern.
+	pub at: Duration,
+
+	/// The pads to send it to.
+	pub side: Side,
+
+	/// The amplitude of the feedback.
+	pub amplitude: u16,
+
+	/// The period of the feedback.
+	pub period: u16,
+
+	/// The number of feedbacks to send.
+	pub count: u16,
+}
+
+/// A timed sequence of pulses.
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct Pattern {
+	pulses: Vec<Pulse>,
+	every:  Option<Duration>,
+	times:  Option<u32>,
+}
+
+fn millis(value: u64) -> Duration {
+	Duration::from_millis(value)
+}
+
+impl Pattern {
+	/// Create an empty pattern.
+	pub fn new() -> Pattern {
+		Pattern::default()
+	}
+
+	/// Add a pulse.
+	pub fn pulse(mut self, at: Duration, side: Side, amplitude: u16, period: u16, count: u16) -> Self {
+		// Pulses are usually added in order, so look from the end.
+		let index = self.pulses.iter().rposition(|p| p.at <= at).map_or(0, |i| i + 1);
+
+		self.pulses.insert(index, Pulse {
+			at:        at,
+			side:      side,
+			amplitude: amplitude,
+			period:    period,
+			count:     count,
+		});
+
+		self
+	}
+
+	/// Add a linear ramp of pulses going from one amplitude to another over
+	/// the given duration.
+	pub fn ramp(mut self, at: Duration, duration: Duration, side: Side, from: u16, to: u16, steps: u16) -> Self {
+		let steps    = if steps < 2 { 2 } else { steps };
+		let duration = duration.as_secs() * 1_000 + (duration.subsec_nanos() / 1_000_000) as u64;
+
+		for step in 0 .. steps {
+			let progress  = step as f64 / (steps - 1) as f64;
+			let amplitude = from as f64 + (to as f64 - from as f64) * progress;
+			let offset    = (duration as f64 * progress).round() as u64;
+
+			self = self.pulse(at + millis(offset), side, amplitude.round() as u16, 0, 1);
+		}
+
+		self
+	}
+
+	/// Add the pulses of another pattern, shifted by the given offset.
+	pub fn then(mut self, at: Duration, other: Pattern) -> Self {
+		for pulse in other.pulses {
+			self = self.pulse(at + pulse.at, pulse.side, pulse.amplitude, pulse.period, pulse.count);
+		}
+
+		self
+	}
+
+	/// Play the pattern again every given period, the given number of times
+	/// in total or until stopped with `None`.
+	pub fn repeat(mut self, every: Duration, times: Option<u32>) -> Self {
+		self.every = Some(every);
+		self.times = times;
+		self
+	}
+
+	/// The pulses in the pattern, sorted by offset.
+	pub fn pulses(&self) -> &[Pulse] {
+		&self.pulses
+	}
+
+	/// The offset of the last pulse.
+	pub fn duration(&self) -> Duration {
+		self.pulses.last().map(|p| p.at).unwrap_or(Duration::from_secs(0))
+	}
+
+	/// A sharp click, like the one when crossing a detent.
+	pub fn detent(side: Side) -> Pattern {
+		Pattern::new()
+			.pulse(millis(0), side, 2048, 0, 1)
+	}
+
+	/// A light tick.
+	pub fn tick(side: Side) -> Pattern {
+		Pattern::new()
+			.pulse(millis(0), side, 512, 0, 1)
+	}
+
+	/// A rumble with the given strength, from `0.0` to `1.0`, with a short
+	/// fade out at the end.
+	pub fn rumble(side: Side, strength: f32, duration: Duration) -> Pattern {
+		let strength  = if strength < 0.0 { 0.0 } else if strength > 1.0 { 1.0 } else { strength };
+		let amplitude = (strength * 1024.0) as u16;
+		let duration  = duration.as_secs() * 1_000 + (duration.subsec_nanos() / 1_000_000) as u64;
+		let fade      = if duration > 100 { 50 } else { duration / 2 };
+
+		let mut pattern = Pattern::new();
+		let mut offset  = 0;
+
+		while offset + fade < duration {
+			pattern = pattern.pulse(millis(offset), side, amplitude, 8_000, 2);
+			offset += 16;
+		}
+
+		pattern.ramp(millis(offset), millis(fade), side, amplitude, 0, (fade / 16) as u16 + 1)
+	}
+
+	/// Parse a pattern from its text representation.
+	pub fn parse<R: Read>(input: R) -> Res<Pattern> {
+		let mut pattern = Pattern::new();
+
+		for line in BufReader::new(input).lines() {
+			let line = try!(line);
+			let line = line.split('#').next().unwrap_or("").trim();
+
+			if line.is_empty() {
+				continue;
+			}
+
+			let fields = line.split_whitespace().collect::<Vec<&str>>();
+
+			if fields.len() != 5 {
+				return Err(Error::InvalidParameter);
+			}
+
+			let at   = try!(fields[0].parse::<u64>().map_err(|_| Error::InvalidParameter));
+			let side = match fields[1] {
+				"left"  => Side::Left,
+				"right" => Side::Right,
+				"both"  => Side::Both,
+				_       => return Err(Error::InvalidParameter),
+			};
+
+			let amplitude = try!(fields[2].parse::<u16>().map_err(|_| Error::InvalidParameter));
+			let period    = try!(fields[3].parse::<u16>().map_err(|_| Error::InvalidParameter));
+			let count     = try!(fields[4].parse::<u16>().map_err(|_| Error::InvalidParameter));
+
+			pattern = pattern.pulse(millis(at), side, amplitude, period, count);
+		}
+
+		Ok(pattern)
+	}
+
+	/// Load a pattern from the given path.
+	pub fn load<P: AsRef<Path>>(path: P) -> Res<Pattern> {
+		Pattern::parse(try!(File::open(path)))
+	}
+}
+
+/// How a newly played pattern interacts with the ones already playing.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Mode {
+	/// Cancel the patterns playing on the same pads.
+	Replace,
+
+	/// Play over the patterns already playing, pulses due at the same time on
+	/// the same pad are merged.
+	Mix,
+}
+
+/// Identifier of a playing pattern.
+pub type Id = u64;
+
+struct Playing {
+	id:      Id,
+	start:   Instant,
+	pattern: Pattern,
+	next:    usize,
+	round:   u32,
+}
+
+impl Playing {
+	fn new(id: Id, start: Instant, pattern: Pattern) -> Playing {
+		Playing {
+			id:      id,
+			start:   start,
+			pattern: pattern,
+			next:    0,
+			round:   1,
+		}
+	}
+
+	/// Start the next round of a repeated pattern once every pulse has been
+	/// sent, returns whether there's one.
+	fn rewind(&mut self) -> bool {
+		if self.next < self.pattern.pulses.len() {
+			return true;
+		}
+
+		match (self.pattern.every, self.pattern.times) {
+			(Some(every), times) if every > Duration::from_secs(0) && times.map_or(true, |t| self.round < t) => {
+				self.start += every;
+				self.next   = 0;
+				self.round += 1;
+
+				!self.pattern.pulses.is_empty()
+			}
+
+			_ =>
+				false
+		}
+	}
+
+	fn sides(&self) -> Option<Side> {
+		self.pattern.pulses[self.next ..].iter().map(|p| p.side).fold(None, |acc, side|
+			match acc {
+				None                => Some(side),
+				Some(s) if s == side => Some(s),
+				Some(_)             => Some(Side::Both),
+			})
+	}
+}
+
+/// Plays patterns concurrently.
+pub struct Scheduler {
+	playing: Vec<Playing>,
+	id:      Id,
+}
+
+impl Default for Scheduler {
+	fn default() -> Self {
+		Scheduler {
+			playing: Vec::new(),
+			id:      0,
+		}
+	}
+}
+
+impl Scheduler {
+	/// Create an empty scheduler.
+	pub fn new() -> Scheduler {
+		Scheduler::default()
+	}
+
+	/// Start playing a pattern now.
+	pub fn play(&mut self, pattern: Pattern, mode: Mode) -> Id {
+		self.play_at(pattern, mode, Instant::now())
+	}
+
+	/// Start playing a pattern at the given instant.
+	pub fn play_at(&mut self, pattern: Pattern, mode: Mode, start: Instant) -> Id {
+		if mode == Mode::Replace {
+			let new = Playing::new(0, start, pattern.clone()).sides();
+
+			self.playing.retain(|p| match (p.sides(), new) {
+				(Some(old), Some(new)) => !old.overlaps(new),
+				_                      => true,
+			});
+		}
+
+		self.id += 1;
+		self.playing.push(Playing::new(self.id, start, pattern));
+
+		self.id
+	}
+
+	/// Stop playing the given pattern.
+	pub fn cancel(&mut self, id: Id) {
+		self.playing.retain(|p| p.id != id);
+	}
+
+	/// Stop playing everything.
+	pub fn stop(&mut self) {
+		self.playing.clear();
+	}
+
+	/// Check if nothing is playing.
+	pub fn is_empty(&self) -> bool {
+		self.playing.is_empty()
+	}
+
+	/// Send the pulses due at the given instant, returns how long until the
+	/// next pulse is due, if any.
+	pub fn tick<T: Transport>(&mut self, controller: &mut Controller<T>, now: Instant) -> Res<Option<Duration>> {
+		// Pending pulses for the left and right pad.
+		let mut pending: [Option<Pulse>; 2] = [None, None];
+
+		for playing in &mut self.playing {
+			while playing.rewind() {
+				let pulse = playing.pattern.pulses[playing.next];
+
+				if playing.start + pulse.at > now {
+					break;
+				}
+
+				playing.next += 1;
+
+				for (index, side) in [Side::Left, Side::Right].iter().enumerate() {
+					if !pulse.side.overlaps(*side) {
+						continue;
+					}
+
+					pending[index] = Some(match pending[index] {
+						Some(current) => Pulse {
+							at:        current.at,
+							side:      *side,
+							amplitude: current.amplitude.saturating_add(pulse.amplitude),
+							period:    if current.period > pulse.period { current.period } else { pulse.period },
+							count:     if current.count > pulse.count { current.count } else { pulse.count },
+						},
+
+						None => Pulse { side: *side, .. pulse },
+					});
+				}
+			}
+		}
+
+		self.playing.retain(|p| p.next < p.pattern.pulses.len());
+
+		for pulse in pending.iter().filter_map(|p| *p) {
+			let feedback = controller.feedback()
+				.amplitude(pulse.amplitude)
+				.period(pulse.period)
+				.count(pulse.count);
+
+			try!(if pulse.side == Side::Left { feedback.left() } else { feedback.right() }.send());
+		}
+
+		Ok(self.playing.iter()
+			.map(|p| p.start + p.pattern.pulses[p.next].at)
+			.min()
+			.map(|at| if at > now { at - now } else { Duration::from_secs(0) }))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::{Duration, Instant};
+	use transport::Mock;
+	use Controller;
+	use super::{Pattern, Scheduler, Side, Mode};
+
+	#[test]
+	fn parse() {
+		let pattern = Pattern::parse(&b"# comment\n80 right 1024 0 1\n0 both 512 10 2 # first\n"[..]).unwrap();
+
+		assert_eq!(2, pattern.pulses().len());
+		assert_eq!(Side::Both, pattern.pulses()[0].side);
+		assert_eq!(Duration::from_millis(80), pattern.duration());
+
+		assert!(Pattern::parse(&b"0 up 1 2 3"[..]).is_err());
+	}
+
+	#[test]
+	fn scheduler() {
+		let mut controller = Controller::new(Mock::new(), 0x1102).unwrap();
+		let mut scheduler  = Scheduler::new();
+		let     start      = Instant::now();
+
+		controller.transport_mut().clear();
+		scheduler.play_at(Pattern::detent(Side::Right).pulse(Duration::from_millis(10), Side::Left, 100, 0, 1), Mode::Mix, start);
+		scheduler.play_at(Pattern::tick(Side::Right), Mode::Mix, start);
+
+		let next = scheduler.tick(&mut controller, start).unwrap();
+		assert_eq!(Some(Duration::from_millis(10)), next);
+
+		// The two pulses on the right pad are merged.
+		assert_eq!(1, controller.transport().sent().len());
+		assert_eq!(&[0x8f, 0x08, 0x00, 0x00, 0x0a][..], &controller.transport().sent()[0][..5]);
+
+		assert_eq!(None, scheduler.tick(&mut controller, start + Duration::from_millis(10)).unwrap());
+		assert_eq!(0x01, controller.transport().sent()[1][2]);
+		assert!(scheduler.is_empty());
+	}
+
+	#[test]
+	fn repeat() {
+		let mut controller = Controller::new(Mock::new(), 0x1102).unwrap();
+		let mut scheduler  = Scheduler::new();
+		let     start      = Instant::now();
+
+		controller.transport_mut().clear();
+		scheduler.play_at(Pattern::tick(Side::Left).repeat(Duration::from_millis(20), Some(2)), Mode::Mix, start);
+
+		assert_eq!(Some(Duration::from_millis(20)), scheduler.tick(&mut controller, start).unwrap());
+		assert_eq!(None, scheduler.tick(&mut controller, start + Duration::from_millis(20)).unwrap());
+		assert_eq!(2, controller.transport().sent().len());
+		assert!(scheduler.is_empty());
+
+		scheduler.play_at(Pattern::tick(Side::Left).repeat(Duration::from_millis(20), None), Mode::Mix, start);
+
+		for round in 0 .. 100 {
+			assert_eq!(Some(Duration::from_millis(20)), scheduler.tick(&mut controller, start + Duration::from_millis(round * 20)).unwrap());
+		}
+
+		assert_eq!(102, controller.transport().sent().len());
+	}
+
+	#[test]
+	fn replace() {
+		let mut scheduler = Scheduler::new();
+
+		scheduler.play(Pattern::rumble(Side::Left, 1.0, Duration::from_millis(200)), Mode::Mix);
+		let id = scheduler.play(Pattern::tick(Side::Right), Mode::Mix);
+		scheduler.play(Pattern::detent(Side::Left), Mode::Replace);
+		scheduler.cancel(id);
+
+		assert_eq!(1, scheduler.playing.len());
+	}
+}
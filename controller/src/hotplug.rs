@@ -0,0 +1,349 @@
+//! Hotplug notifications.
+//!
+//! On Linux the libusb hotplug support is used when available, otherwise the
+//! attached controllers are polled.
+
+use std::ops::Deref;
+use std::thread;
+use std::time::Duration;
+use std::sync::mpsc::{channel, Sender, Receiver};
+use {Result as Res, Manager, Device};
+
+/// Hotplug event.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Event {
+	/// A controller has been attached, controllers in bootloader mode are
+	/// notified too.
+	Arrived(Device),
+
+	/// A controller has been detached.
+	Left(Device),
+}
+
+/// Hotplug notifications, the controllers already attached are notified as
+/// arrived first.
+pub struct Hotplug {
+	rx: Receiver<Event>,
+}
+
+impl Deref for Hotplug {
+	type Target = Receiver<Event>;
+
+	fn deref(&self) -> &Self::Target {
+		&self.rx
+	}
+}
+
+/// Check if the two descriptions are of the same controller, the serial can
+/// only be read sometimes so it's ignored when the position on the bus is
+/// known.
+fn same(a: &Device, b: &Device) -> bool {
+	a.product == b.product && a.bus == b.bus && a.address == b.address && a.index == b.index &&
+		((a.bus != 0 && a.address != 0) || a.serial == b.serial)
+}
+
+/// Keeps track of the attached controllers.
+struct Tracker {
+	devices: Vec<Device>,
+	tx:      Sender<Event>,
+}
+
+impl Tracker {
+	fn new(tx: Sender<Event>) -> Tracker {
+		Tracker {
+			devices: Vec::new(),
+			tx:      tx,
+		}
+	}
+
+	fn arrived(&mut self, device: Device) -> Result<(), ()> {
+		if self.devices.iter().any(|d| same(d, &device)) {
+			return Ok(());
+		}
+
+		self.devices.push(device.clone());
+		self.tx.send(Event::Arrived(device)).map_err(|_| ())
+	}
+
+	fn left<F: Fn(&Device) -> bool>(&mut self, func: F) -> Result<(), ()> {
+		let (left, devices) = self.devices.drain(..).partition::<Vec<_>, _>(|d| func(d));
+		self.devices = devices;
+
+		for device in left {
+			try!(self.tx.send(Event::Left(device)).map_err(|_| ()));
+		}
+
+		Ok(())
+	}
+
+	fn sync(&mut self, devices: Vec<Device>) -> Result<(), ()> {
+		try!(self.left(|d| !devices.iter().any(|n| same(d, n))));
+
+		for device in devices {
+			try!(self.arrived(device));
+		}
+
+		Ok(())
+	}
+}
+
+/// The attached controllers, including the ones in bootloader mode.
+fn attached(manager: &Manager) -> Res<Vec<Device>> {
+	let mut devices = try!(manager.devices());
+	devices.extend(try!(manager.bootloaders()));
+
+	Ok(devices)
+}
+
+#[doc(hidden)]
+pub fn spawn() -> Hotplug {
+	let (tx, rx) = channel();
+
+	#[cfg(target_os = "linux")]
+	let tx = match native::Context::new() {
+		Some(context) => {
+			thread::spawn(move || native::run(context, tx));
+
+			return Hotplug {
+				rx: rx
+			};
+		}
+
+		None =>
+			tx
+	};
+
+	thread::spawn(move || poll(Tracker::new(tx)));
+
+	Hotplug {
+		rx: rx
+	}
+}
+
+fn poll(mut tracker: Tracker) {
+	let manager = if let Ok(manager) = Manager::new() {
+		manager
+	}
+	else {
+		return;
+	};
+
+	loop {
+		if let Ok(devices) = attached(&manager) {
+			if tracker.sync(devices).is_err() {
+				break;
+			}
+		}
+
+		thread::sleep(Duration::from_millis(500));
+	}
+}
+
+#[cfg(target_os = "linux")]
+mod native {
+	use std::ptr;
+	use std::time::Duration;
+	use std::sync::mpsc::{channel, Sender};
+	use std::os::raw::{c_int, c_long, c_void};
+	use {Manager, VENDOR_ID};
+	use super::{Event, Tracker, poll, attached};
+
+	#[allow(non_camel_case_types)]
+	mod ffi {
+		use std::os::raw::{c_int, c_long, c_void};
+
+		pub enum libusb_context { }
+		pub enum libusb_device { }
+
+		#[repr(C)]
+		pub struct timeval {
+			pub tv_sec:  c_long,
+			pub tv_usec: c_long,
+		}
+
+		pub type libusb_hotplug_callback_fn = extern "C" fn(*mut libusb_context, *mut libusb_device, c_int, *mut c_void) -> c_int;
+
+		pub const LIBUSB_CAP_HAS_HOTPLUG:              u32   = 0x0001;
+		pub const LIBUSB_HOTPLUG_EVENT_DEVICE_ARRIVED: c_int = 0x01;
+		pub const LIBUSB_HOTPLUG_EVENT_DEVICE_LEFT:    c_int = 0x02;
+		pub const LIBUSB_HOTPLUG_MATCH_ANY:            c_int = -1;
+		pub const LIBUSB_HOTPLUG_ENUMERATE:            c_int = 0x01;
+
+		// The library is linked by `libusb-sys`.
+		extern "C" {
+			pub fn libusb_init(context: *mut *mut libusb_context) -> c_int;
+			pub fn libusb_exit(context: *mut libusb_context);
+			pub fn libusb_has_capability(capability: u32) -> c_int;
+			pub fn libusb_handle_events_timeout(context: *mut libusb_context, tv: *const timeval) -> c_int;
+			pub fn libusb_get_bus_number(device: *mut libusb_device) -> u8;
+			pub fn libusb_get_device_address(device: *mut libusb_device) -> u8;
+
+			pub fn libusb_hotplug_register_callback(context: *mut libusb_context, events: c_int, flags: c_int,
+				vendor_id: c_int, product_id: c_int, dev_class: c_int,
+				cb_fn: libusb_hotplug_callback_fn, user_data: *mut c_void, handle: *mut c_int) -> c_int;
+
+			pub fn libusb_hotplug_deregister_callback(context: *mut libusb_context, handle: c_int);
+		}
+	}
+
+	/// A libusb context only used for hotplug events.
+	pub struct Context(*mut ffi::libusb_context);
+
+	unsafe impl Send for Context { }
+
+	impl Context {
+		pub fn new() -> Option<Context> {
+			unsafe {
+				if ffi::libusb_has_capability(ffi::LIBUSB_CAP_HAS_HOTPLUG) == 0 {
+					return None;
+				}
+
+				let mut context = ptr::null_mut();
+
+				if ffi::libusb_init(&mut context) != 0 {
+					return None;
+				}
+
+				Some(Context(context))
+			}
+		}
+
+		fn handle(&self, timeout: Duration) {
+			let tv = ffi::timeval {
+				tv_sec:  timeout.as_secs() as c_long,
+				tv_usec: (timeout.subsec_nanos() / 1_000) as c_long,
+			};
+
+			unsafe {
+				ffi::libusb_handle_events_timeout(self.0, &tv);
+			}
+		}
+	}
+
+	impl Drop for Context {
+		fn drop(&mut self) {
+			unsafe {
+				ffi::libusb_exit(self.0);
+			}
+		}
+	}
+
+	extern "C" fn callback(_context: *mut ffi::libusb_context, device: *mut ffi::libusb_device, event: c_int, data: *mut c_void) -> c_int {
+		let tx = unsafe { &*(data as *const Sender<(bool, u8, u8)>) };
+
+		let (bus, address) = unsafe {
+			(ffi::libusb_get_bus_number(device), ffi::libusb_get_device_address(device))
+		};
+
+		// Returning non-zero deregisters the callback.
+		if tx.send((event == ffi::LIBUSB_HOTPLUG_EVENT_DEVICE_ARRIVED, bus, address)).is_ok() {
+			0
+		}
+		else {
+			1
+		}
+	}
+
+	pub fn run(context: Context, tx: Sender<Event>) {
+		let manager = if let Ok(manager) = Manager::new() {
+			manager
+		}
+		else {
+			return;
+		};
+
+		let mut tracker = Tracker::new(tx);
+
+		let (raw, events) = channel::<(bool, u8, u8)>();
+		let     data      = Box::into_raw(Box::new(raw));
+		let mut handle    = 0;
+
+		// Register before looking at what's attached so nothing plugged in
+		// meanwhile is missed, the tracker ignores devices reported twice.
+		let result = unsafe {
+			ffi::libusb_hotplug_register_callback(context.0,
+				ffi::LIBUSB_HOTPLUG_EVENT_DEVICE_ARRIVED | ffi::LIBUSB_HOTPLUG_EVENT_DEVICE_LEFT, ffi::LIBUSB_HOTPLUG_ENUMERATE,
+				VENDOR_ID as c_int, ffi::LIBUSB_HOTPLUG_MATCH_ANY, ffi::LIBUSB_HOTPLUG_MATCH_ANY,
+				callback, data as *mut c_void, &mut handle)
+		};
+
+		if result == 0 {
+			let mut done = tracker.sync(attached(&manager).unwrap_or(Vec::new())).is_err();
+
+			while !done {
+				context.handle(Duration::from_secs(1));
+
+				while let Ok((arrived, bus, address)) = events.try_recv() {
+					let result = if arrived {
+						attached(&manager).unwrap_or(Vec::new()).into_iter()
+							.filter(|d| d.bus == bus && d.address == address)
+							.map(|d| tracker.arrived(d))
+							.collect::<Result<(), ()>>()
+					}
+					else {
+						tracker.left(|d| d.bus == bus && d.address == address)
+					};
+
+					if result.is_err() {
+						done = true;
+						break;
+					}
+				}
+			}
+
+			unsafe {
+				ffi::libusb_hotplug_deregister_callback(context.0, handle);
+				drop(Box::from_raw(data));
+			}
+		}
+		else {
+			unsafe {
+				drop(Box::from_raw(data));
+			}
+
+			poll(tracker);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::mpsc::channel;
+	use Device;
+	use super::{Event, Tracker};
+
+	fn device(address: u8, serial: Option<&str>) -> Device {
+		Device {
+			product: 0x1102,
+			bus:     1,
+			address: address,
+			index:   0,
+			serial:  serial.map(|s| s.to_owned()),
+		}
+	}
+
+	#[test]
+	fn tracker() {
+		let (tx, rx)    = channel();
+		let mut tracker = Tracker::new(tx);
+
+		// Devices reported twice only arrive once, the serial isn't always
+		// readable.
+		tracker.arrived(device(2, Some("A"))).unwrap();
+		tracker.arrived(device(2, None)).unwrap();
+		tracker.arrived(device(3, None)).unwrap();
+		assert_eq!(vec![Event::Arrived(device(2, Some("A"))), Event::Arrived(device(3, None))], rx.try_iter().collect::<Vec<_>>());
+
+		tracker.left(|d| d.address == 2).unwrap();
+		assert_eq!(vec![Event::Left(device(2, Some("A")))], rx.try_iter().collect::<Vec<_>>());
+
+		tracker.sync(vec![device(3, None), device(4, None)]).unwrap();
+		assert_eq!(vec![Event::Arrived(device(4, None))], rx.try_iter().collect::<Vec<_>>());
+
+		tracker.sync(vec![device(4, None)]).unwrap();
+		assert_eq!(vec![Event::Left(device(3, None))], rx.try_iter().collect::<Vec<_>>());
+
+		drop(rx);
+		assert!(tracker.arrived(device(5, None)).is_err());
+	}
+}
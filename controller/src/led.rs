@@ -1,14 +1,13 @@
-use std::io::Write;
-use {Result as Res, Controller};
+use {Result as Res, Controller, Transport};
 
 /// Controller led management.
-pub struct Led<'a, 'b: 'a> {
-	controller: &'a mut Controller<'b>,
+pub struct Led<'a, T: Transport + 'a> {
+	controller: &'a mut Controller<T>,
 }
 
-impl<'a, 'b> Led<'a, 'b> {
+impl<'a, T: Transport> Led<'a, T> {
 	#[doc(hidden)]
-	pub fn new(controller: &'a mut Controller<'b>) -> Led<'a, 'b> {
+	pub fn new(controller: &'a mut Controller<T>) -> Led<'a, T> {
 		Led {
 			controller: controller,
 		}
@@ -16,9 +15,7 @@ impl<'a, 'b> Led<'a, 'b> {
 
 	/// Change the led luminosity.
 	pub fn level(self, value: u8) -> Res<()> {
-		self.controller.control_with(0x87, 0x03, |mut buf| {
-			buf.write(&[0x2d, value])
-		})
+		self.controller.settings().led(value)
 	}
 
 	/// Turn the led off.
@@ -9,25 +9,50 @@ extern crate libusb as usb;
 #[cfg(not(target_os = "linux"))]
 extern crate hid;
 
+#[cfg(feature = "futures")]
+extern crate futures;
+
 const VENDOR_ID:  u16       = 0x28de;
 const PRODUCT_ID: [u16;  2] = [0x1102, 0x1142];
 const ENDPOINT:   [u8;   2] = [3, 2];
 const INDEX:      [u16;  2] = [2, 1];
+const SLOTS:      [u16;  2] = [1, 4];
+const BOOTLOADER: [u16;  2] = [0x1002, 0x1042];
 
 mod error;
 pub use error::Error;
 
 pub type Result<T> = ::std::result::Result<T, Error>;
 
+pub mod transport;
+pub use transport::Transport;
+
 mod manager;
-pub use manager::Manager;
+pub use manager::{Manager, Device};
+
+pub mod hotplug;
+pub use hotplug::Hotplug;
 
 mod controller;
 pub use controller::Controller;
 
+mod events;
+pub use events::Events;
+
+#[cfg(feature = "futures")]
+pub use events::{Listener, Command};
+
+mod policy;
+pub use policy::{Policy, Backoff, Metrics};
+
 mod feedback;
 pub use feedback::Feedback;
 
+pub mod haptic;
+
+pub mod settings;
+pub use settings::Settings;
+
 mod sensors;
 pub use sensors::Sensors;
 
@@ -37,8 +62,8 @@ pub use led::Led;
 pub mod sound;
 pub use sound::Sound;
 
-mod calibrate;
-pub use calibrate::Calibrate;
+pub mod calibrate;
+pub use calibrate::{Calibrate, Calibration};
 
 mod lizard;
 pub use lizard::Lizard;
@@ -47,7 +72,9 @@ pub mod button;
 pub use button::Button;
 
 mod state;
-pub use state::{State, Axis, Trigger, Pad, Angles};
+pub use state::{State, Battery, Axis, Trigger, Pad, Vector, Quaternion, Angles};
 
 pub mod details;
 pub use details::Details;
+
+pub mod capture;
@@ -1,13 +1,13 @@
-use {Result as Res, Controller};
+use {Result as Res, Controller, Transport};
 
 /// Controller led management.
-pub struct Lizard<'a, 'b: 'a> {
-	controller: &'a mut Controller<'b>,
+pub struct Lizard<'a, T: Transport + 'a> {
+	controller: &'a mut Controller<T>,
 }
 
-impl<'a, 'b> Lizard<'a, 'b> {
+impl<'a, T: Transport> Lizard<'a, T> {
 	#[doc(hidden)]
-	pub fn new(controller: &'a mut Controller<'b>) -> Lizard<'a, 'b> {
+	pub fn new(controller: &'a mut Controller<T>) -> Lizard<'a, T> {
 		Lizard {
 			controller: controller,
 		}
@@ -15,13 +15,13 @@ impl<'a, 'b> Lizard<'a, 'b> {
 
 	/// Enable lizard mode.
 	pub fn enable(self) -> Res<()> {
-		self.controller.settings().lizard = true;
+		self.controller.shadow().lizard = true;
 		self.controller.reset()
 	}
 
 	/// Disable lizard mode.
 	pub fn disable(self) -> Res<()> {
-		self.controller.settings().lizard = false;
+		self.controller.shadow().lizard = false;
 		self.controller.reset()
 	}
 }
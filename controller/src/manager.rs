@@ -1,11 +1,56 @@
+#[cfg(target_os = "linux")]
+use std::time::Duration;
+
 #[cfg(target_os = "linux")]
 use usb;
 
 #[cfg(not(target_os = "linux"))]
 use hid;
 
-use {Result as Res, Controller};
-use {VENDOR_ID, PRODUCT_ID, ENDPOINT, INDEX};
+use {Result as Res, Error, Controller, Policy, Hotplug, transport, hotplug};
+
+#[cfg(feature = "futures")]
+use Listener;
+use {VENDOR_ID, PRODUCT_ID, ENDPOINT, INDEX, SLOTS, BOOTLOADER};
+
+/// A controller attached to the system.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Device {
+	/// The USB product ID.
+	pub product: u16,
+
+	/// The USB bus number.
+	pub bus: u8,
+
+	/// The USB device address.
+	pub address: u8,
+
+	/// The interface index, the wireless receiver has one for each
+	/// controller.
+	pub index: u16,
+
+	/// The USB serial number, controllers on the same wireless receiver share
+	/// it.
+	pub serial: Option<String>,
+}
+
+impl Device {
+	/// Check if the controller is remote.
+	pub fn is_remote(&self) -> bool {
+		self.product == 0x1142
+	}
+
+	/// Check if the controller is wired.
+	pub fn is_wired(&self) -> bool {
+		self.product == 0x1102
+	}
+
+	/// Check if the controller is in bootloader mode, it can't be opened until
+	/// the firmware update is done.
+	pub fn is_bootloader(&self) -> bool {
+		BOOTLOADER.contains(&self.product)
+	}
+}
 
 /// Controller manager.
 pub struct Manager {
@@ -14,6 +59,8 @@ pub struct Manager {
 
 	#[cfg(not(target_os = "linux"))]
 	hid: hid::Manager,
+
+	policy: Policy,
 }
 
 impl Manager {
@@ -21,53 +68,268 @@ impl Manager {
 	#[cfg(target_os = "linux")]
 	pub fn new() -> Res<Manager> {
 		Ok(Manager {
-			usb: try!(usb::Context::new()),
+			usb:    try!(usb::Context::new()),
+			policy: Policy::default(),
 		})
 	}
 
 	#[cfg(not(target_os = "linux"))]
 	pub fn new() -> Res<Manager> {
 		Ok(Manager {
-			hid: try!(hid::init()),
+			hid:    try!(hid::init()),
+			policy: Policy::default(),
 		})
 	}
 
-	/// Open a controller.
+	/// Set how requests are retried on the controllers opened from now on,
+	/// including the ones sent while opening them.
+	pub fn policy(mut self, value: Policy) -> Self {
+		self.policy = value;
+		self
+	}
+
+	/// List the attached controllers.
 	#[cfg(target_os = "linux")]
-	pub fn open(&mut self) -> Res<Controller> {
-		let devices = try!(self.usb.devices());
+	pub fn devices(&self) -> Res<Vec<Device>> {
+		let mut result = Vec::new();
 
-		for mut device in devices.iter() {
+		for device in try!(self.usb.devices()).iter() {
 			let descriptor = try!(device.device_descriptor());
 
 			if descriptor.vendor_id() != VENDOR_ID {
 				continue;
 			}
 
-			for (&product, (&endpoint, &index)) in PRODUCT_ID.iter().zip(ENDPOINT.iter().zip(INDEX.iter())) {
+			for (&product, (&index, &slots)) in PRODUCT_ID.iter().zip(INDEX.iter().zip(SLOTS.iter())) {
 				if descriptor.product_id() != product {
 					continue;
 				}
-				
-				let handle = try!(device.open());
 
-				return Controller::new(device, handle, product, endpoint, index);
+				let serial = device.open().ok().and_then(|handle| {
+					handle.read_languages(Duration::from_secs(1)).ok()
+						.and_then(|languages| languages.first().cloned())
+						.and_then(|language| handle.read_serial_number_string(language, &descriptor, Duration::from_secs(1)).ok())
+				});
+
+				for slot in 0 .. slots {
+					result.push(Device {
+						product: product,
+						bus:     device.bus_number(),
+						address: device.address(),
+						index:   index + slot,
+						serial:  serial.clone(),
+					});
+				}
 			}
 		}
 
-		return Err(usb::Error::NoDevice.into());
+		Ok(result)
 	}
 
 	#[cfg(not(target_os = "linux"))]
-	pub fn open(&self) -> Res<Controller> {
-		for &product in &PRODUCT_ID {
+	pub fn devices(&self) -> Res<Vec<Device>> {
+		let mut result = Vec::new();
+
+		for (&product, (&index, &slots)) in PRODUCT_ID.iter().zip(INDEX.iter().zip(SLOTS.iter())) {
 			for device in self.hid.find(Some(VENDOR_ID), Some(product)) {
-				if let Ok(handle) = device.open() {
-					return Controller::new(handle, product);
+				let number = device.interface_number() as u16;
+
+				if number < index || number >= index + slots {
+					continue;
 				}
+
+				result.push(Device {
+					product: product,
+					bus:     0,
+					address: 0,
+					index:   number,
+					serial:  device.serial_number(),
+				});
 			}
 		}
 
-		return Err(hid::Error::NotFound.into());
+		Ok(result)
+	}
+
+	/// List the attached controllers in bootloader mode.
+	#[cfg(target_os = "linux")]
+	pub fn bootloaders(&self) -> Res<Vec<Device>> {
+		let mut result = Vec::new();
+
+		for device in try!(self.usb.devices()).iter() {
+			let descriptor = try!(device.device_descriptor());
+
+			if descriptor.vendor_id() != VENDOR_ID || !BOOTLOADER.contains(&descriptor.product_id()) {
+				continue;
+			}
+
+			result.push(Device {
+				product: descriptor.product_id(),
+				bus:     device.bus_number(),
+				address: device.address(),
+				index:   0,
+				serial:  None,
+			});
+		}
+
+		Ok(result)
+	}
+
+	#[cfg(not(target_os = "linux"))]
+	pub fn bootloaders(&self) -> Res<Vec<Device>> {
+		let mut result = Vec::new();
+
+		for &product in &BOOTLOADER {
+			for device in self.hid.find(Some(VENDOR_ID), Some(product)) {
+				result.push(Device {
+					product: product,
+					bus:     0,
+					address: 0,
+					index:   device.interface_number() as u16,
+					serial:  device.serial_number(),
+				});
+			}
+		}
+
+		Ok(result)
+	}
+
+	/// The error for when no controller can be found, a controller in
+	/// bootloader mode is reported as such.
+	fn absent(&self) -> Error {
+		if self.bootloaders().map(|d| !d.is_empty()).unwrap_or(false) {
+			Error::Bootloader
+		}
+		else {
+			Error::NotFound
+		}
+	}
+
+	/// Get notified when controllers are attached or detached.
+	pub fn hotplug(&self) -> Hotplug {
+		hotplug::spawn()
+	}
+
+	/// Read the states of the given controller on a dedicated thread.
+	#[cfg(feature = "futures")]
+	pub fn listen(&self, device: &Device) -> Listener {
+		Listener::open(device.clone(), self.policy)
+	}
+
+	/// Open the given controller.
+	#[cfg(target_os = "linux")]
+	pub fn open_device(&self, info: &Device) -> Res<Controller<transport::Usb>> {
+		if info.is_bootloader() {
+			return Err(Error::Bootloader);
+		}
+
+		let devices = try!(self.usb.devices());
+
+		for device in devices.iter() {
+			if device.bus_number() != info.bus || device.address() != info.address {
+				continue;
+			}
+
+			for (&product, (&endpoint, &index)) in PRODUCT_ID.iter().zip(ENDPOINT.iter().zip(INDEX.iter())) {
+				if info.product != product {
+					continue;
+				}
+
+				let handle   = try!(device.open());
+				let endpoint = endpoint + (info.index - index) as u8;
+
+				return Controller::with_policy(try!(transport::Usb::new(device, handle, endpoint, info.index)), product, self.policy);
+			}
+		}
+
+		Err(Error::NotFound)
+	}
+
+	#[cfg(not(target_os = "linux"))]
+	pub fn open_device(&self, info: &Device) -> Res<Controller<transport::Hid>> {
+		if info.is_bootloader() {
+			return Err(Error::Bootloader);
+		}
+
+		for device in self.hid.find(Some(VENDOR_ID), Some(info.product)) {
+			if device.interface_number() as u16 != info.index || device.serial_number() != info.serial {
+				continue;
+			}
+
+			// The controller was just found, so failing to open it means access
+			// to it was denied.
+			let handle = try!(device.open().map_err(|_| Error::PermissionDenied));
+
+			return Controller::with_policy(try!(transport::Hid::new(handle)), info.product, self.policy);
+		}
+
+		Err(Error::NotFound)
+	}
+
+	/// Open the first controller that can be opened.
+	#[cfg(target_os = "linux")]
+	pub fn open(&mut self) -> Res<Controller<transport::Usb>> {
+		let mut error = None;
+
+		for device in try!(self.devices()) {
+			match self.open_device(&device) {
+				Ok(controller) =>
+					return Ok(controller),
+
+				Err(err) =>
+					error = Some(err),
+			}
+		}
+
+		Err(error.unwrap_or_else(|| self.absent()))
+	}
+
+	#[cfg(not(target_os = "linux"))]
+	pub fn open(&self) -> Res<Controller<transport::Hid>> {
+		let mut error = None;
+
+		for device in try!(self.devices()) {
+			match self.open_device(&device) {
+				Ok(controller) =>
+					return Ok(controller),
+
+				Err(err) =>
+					error = Some(err),
+			}
+		}
+
+		Err(error.unwrap_or_else(|| self.absent()))
+	}
+
+	/// Open the nth attached controller, in the order of `devices()`.
+	#[cfg(target_os = "linux")]
+	pub fn open_nth(&self, n: usize) -> Res<Controller<transport::Usb>> {
+		let device = try!(try!(self.devices()).into_iter().nth(n).ok_or_else(|| self.absent()));
+		self.open_device(&device)
+	}
+
+	#[cfg(not(target_os = "linux"))]
+	pub fn open_nth(&self, n: usize) -> Res<Controller<transport::Hid>> {
+		let device = try!(try!(self.devices()).into_iter().nth(n).ok_or_else(|| self.absent()));
+		self.open_device(&device)
+	}
+
+	/// Open the first controller with the given USB serial number.
+	#[cfg(target_os = "linux")]
+	pub fn open_by_serial(&self, serial: &str) -> Res<Controller<transport::Usb>> {
+		let device = try!(try!(self.devices()).into_iter()
+			.find(|d| d.serial.as_ref().map(|s| &**s) == Some(serial))
+			.ok_or(Error::NotFound));
+
+		self.open_device(&device)
+	}
+
+	#[cfg(not(target_os = "linux"))]
+	pub fn open_by_serial(&self, serial: &str) -> Res<Controller<transport::Hid>> {
+		let device = try!(try!(self.devices()).into_iter()
+			.find(|d| d.serial.as_ref().map(|s| &**s) == Some(serial))
+			.ok_or(Error::NotFound));
+
+		self.open_device(&device)
 	}
 }
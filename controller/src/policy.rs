@@ -0,0 +1,176 @@
+use std::cmp;
+use std::time::Duration;
+
+/// How the delay between retries grows.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Backoff {
+	/// The same delay before every retry.
+	Constant(Duration),
+
+	/// The delay grows by the given step, the first retry is immediate.
+	Linear(Duration),
+
+	/// The delay doubles from the initial one up to the maximum.
+	Exponential {
+		initial: Duration,
+		maximum: Duration,
+	},
+}
+
+/// How requests are retried when the controller doesn't answer.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Policy {
+	retries: u32,
+	backoff: Backoff,
+	timeout: Duration,
+}
+
+impl Default for Policy {
+	fn default() -> Self {
+		Policy {
+			retries: 10,
+			backoff: Backoff::Linear(Duration::from_millis(50)),
+			timeout: Duration::from_secs(0),
+		}
+	}
+}
+
+impl Policy {
+	/// Create the default policy.
+	pub fn new() -> Policy {
+		Policy::default()
+	}
+
+	/// A policy for interactive use, giving up quickly.
+	pub fn fast() -> Policy {
+		Policy::new()
+			.retries(2)
+			.backoff(Backoff::Constant(Duration::from_millis(10)))
+			.timeout(Duration::from_millis(200))
+	}
+
+	/// The number of retries before giving up.
+	pub fn retries(mut self, value: u32) -> Self {
+		self.retries = value;
+		self
+	}
+
+	/// The delay between retries.
+	pub fn backoff(mut self, value: Backoff) -> Self {
+		self.backoff = value;
+		self
+	}
+
+	/// The timeout for each transfer, zero waits forever; it's ignored by the
+	/// HID transport.
+	pub fn timeout(mut self, value: Duration) -> Self {
+		self.timeout = value;
+		self
+	}
+
+	/// Get the number of retries.
+	pub fn get_retries(&self) -> u32 {
+		self.retries
+	}
+
+	/// Get the timeout for each transfer.
+	pub fn get_timeout(&self) -> Duration {
+		self.timeout
+	}
+
+	/// The delay before the given retry, starting from zero.
+	pub fn delay(&self, retry: u32) -> Duration {
+		match self.backoff {
+			Backoff::Constant(delay) =>
+				delay,
+
+			Backoff::Linear(step) =>
+				step * retry,
+
+			Backoff::Exponential { initial, maximum } =>
+				cmp::min(initial * (1 << cmp::min(retry, 16)), maximum),
+		}
+	}
+}
+
+/// Statistics about the requests sent to the controller.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct Metrics {
+	/// The number of requests.
+	pub requests: u64,
+
+	/// The number of retries over all requests.
+	pub retries: u64,
+
+	/// The most retries a single request needed.
+	pub most: u32,
+
+	/// The number of requests given up on.
+	pub failures: u64,
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+	use transport::Mock;
+	use {Result as Res, Controller, Transport};
+	use super::{Policy, Backoff, Metrics};
+
+	#[test]
+	fn retries() {
+		let policy = Policy::new().backoff(Backoff::Exponential {
+			initial: Duration::from_millis(10),
+			maximum: Duration::from_millis(50),
+		});
+
+		assert_eq!(Duration::from_millis(10), policy.delay(0));
+		assert_eq!(Duration::from_millis(40), policy.delay(2));
+		assert_eq!(Duration::from_millis(50), policy.delay(3));
+		assert_eq!(Duration::from_millis(100), Policy::new().delay(2));
+
+		let mut mock = Mock::new();
+		mock.reply(0xb4, &[]).reply(0xb4, &[0x02]);
+
+		let mut controller = Controller::new(mock, 0x1142).unwrap();
+		controller.policy(Policy::new().retries(1).backoff(Backoff::Constant(Duration::from_millis(0))));
+
+		assert!(controller.is_connected());
+		assert!(!controller.is_connected());
+		assert_eq!(Metrics { requests: 2, retries: 2, most: 1, failures: 1 }, controller.metrics());
+	}
+
+	/// Records the timeout each feature report is sent with.
+	struct Timed {
+		mock:    Mock,
+		timeout: Duration,
+		sent:    Vec<Duration>,
+	}
+
+	impl Transport for Timed {
+		fn send_feature(&mut self, packet: &[u8]) -> Res<()> {
+			self.sent.push(self.timeout);
+			self.mock.send_feature(packet)
+		}
+
+		fn get_feature(&mut self, packet: &mut [u8]) -> Res<()> {
+			self.mock.get_feature(packet)
+		}
+
+		fn read_interrupt(&mut self, packet: &mut [u8], timeout: Duration) -> Res<usize> {
+			self.mock.read_interrupt(packet, timeout)
+		}
+
+		fn timeout(&mut self, value: Duration) {
+			self.timeout = value;
+		}
+	}
+
+	#[test]
+	fn open() {
+		let transport  = Timed { mock: Mock::new(), timeout: Duration::from_secs(0), sent: Vec::new() };
+		let controller = Controller::with_policy(transport, 0x1102, Policy::fast()).unwrap();
+
+		assert!(!controller.transport().sent.is_empty());
+		assert!(controller.transport().sent.iter().all(|&t| t == Duration::from_millis(200)));
+	}
+}
@@ -1,13 +1,14 @@
-use {Result as Res, Controller};
+use {Result as Res, Controller, Transport};
+use settings::{self, Imu};
 
 /// Controller sensors management.
-pub struct Sensors<'a, 'b: 'a> {
-	controller: &'a mut Controller<'b>,
+pub struct Sensors<'a, T: Transport + 'a> {
+	controller: &'a mut Controller<T>,
 }
 
-impl<'a, 'b> Sensors<'a, 'b> {
+impl<'a, T: Transport> Sensors<'a, T> {
 	#[doc(hidden)]
-	pub fn new(controller: &'a mut Controller<'b>) -> Sensors<'a, 'b> {
+	pub fn new(controller: &'a mut Controller<T>) -> Sensors<'a, T> {
 		Sensors {
 			controller: controller,
 		}
@@ -15,13 +16,27 @@ impl<'a, 'b> Sensors<'a, 'b> {
 
 	/// Turn the sensors off.
 	pub fn off(self) -> Res<()> {
-		self.controller.settings().sensors = false;
-		self.controller.reset()
+		self.controller.settings().imu(Imu::empty())
 	}
 
 	/// Turn the sensors on.
 	pub fn on(self) -> Res<()> {
-		self.controller.settings().sensors = true;
-		self.controller.reset()
+		self.controller.settings().imu(settings::ORIENTATION | settings::ACCELERATION | settings::GYROSCOPE)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use transport::Mock;
+	use Controller;
+
+	#[test]
+	fn on() {
+		let mut controller = Controller::new(Mock::new(), 0x1102).unwrap();
+		controller.transport_mut().clear();
+
+		// The raw accelerometer data is only sent when asked for.
+		controller.sensors().on().unwrap();
+		assert_eq!(&[0x87, 0x03, 0x30, 0x1c, 0x00][..], &controller.transport().sent()[0][..5]);
 	}
 }
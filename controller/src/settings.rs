@@ -0,0 +1,279 @@
+//! Settings registers.
+//!
+//! Settings are written with a `0x87` request as a list of register and
+//! little endian `u16` value pairs, the controller forgets them when it's
+//! turned off so a shadow copy is kept and written again on reset.
+//!
+//! There is no known request to read the registers back from the device, so
+//! `Settings::get` returns the last value written through this API, or
+//! `None` for registers that were never written.
+
+use std::time::Duration;
+use byteorder::{WriteBytesExt, LittleEndian};
+use {Result as Res, Error, Controller, Transport};
+
+/// Maximum number of registers written by a single request.
+const LIMIT: usize = 20;
+
+/// A settings register.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Register {
+	MouseSensitivity                 = 0x00,
+	MouseAcceleration                = 0x01,
+	TrackballRotationAngle           = 0x02,
+	LeftGamepadStickEnabled          = 0x04,
+	RightGamepadStickEnabled         = 0x05,
+	UsbDebugMode                     = 0x06,
+	LeftTrackpadMode                 = 0x07,
+	RightTrackpadMode                = 0x08,
+	MousePointerEnabled              = 0x09,
+	DPadDeadzone                     = 0x0a,
+	MinimumMomentumVelocity          = 0x0b,
+	MomentumDecayAmount              = 0x0c,
+	TrackpadRelativeTicksPerPixel    = 0x0d,
+	HapticIncrement                  = 0x0e,
+	DPadAngleSin                     = 0x0f,
+	DPadAngleCos                     = 0x10,
+	MomentumVerticalDivisor          = 0x11,
+	MomentumMaximumVelocity          = 0x12,
+	TrackpadZOn                      = 0x13,
+	TrackpadZOff                     = 0x14,
+	SensitivityScaleAmount           = 0x15,
+	LeftTrackpadSecondaryMode        = 0x16,
+	RightTrackpadSecondaryMode       = 0x17,
+	SmoothAbsoluteMouse              = 0x18,
+	HomeButtonPowerOffTime           = 0x19,
+	TrackpadOuterRadius              = 0x1b,
+	TrackpadZOnLeft                  = 0x1c,
+	TrackpadZOffLeft                 = 0x1d,
+	TrackpadOuterSpinVelocity        = 0x1e,
+	TrackpadOuterSpinRadius          = 0x1f,
+	TrackpadOuterSpinHorizontalOnly  = 0x20,
+	TrackpadRelativeDeadzone         = 0x21,
+	TrackpadRelativeMaximumVelocity  = 0x22,
+	TrackpadRelativeInvertY          = 0x23,
+	TrackpadDoubleTapBeepEnabled     = 0x24,
+	TrackpadDoubleTapBeepPeriod      = 0x25,
+	TrackpadDoubleTapBeepCount       = 0x26,
+	TrackpadOuterRadiusRelease       = 0x27,
+	RadialModeAngle                  = 0x28,
+	HapticIntensityMouseMode         = 0x29,
+	LeftDPadRequiresClick            = 0x2a,
+	RightDPadRequiresClick           = 0x2b,
+	LedBaselineBrightness            = 0x2c,
+	LedBrightness                    = 0x2d,
+	RawJoystick                      = 0x2e,
+	FastScan                         = 0x2f,
+	Imu                              = 0x30,
+	WirelessPacketVersion            = 0x31,
+	SleepTimeout                     = 0x32,
+}
+
+/// What a trackpad does in lizard mode.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Trackpad {
+	AbsoluteMouse      = 0x00,
+	RelativeMouse      = 0x01,
+	DPadFourWay        = 0x02,
+	DPadFourWayOverlap = 0x03,
+	DPadEightWay       = 0x04,
+	Radial             = 0x05,
+	AbsoluteDPad       = 0x06,
+	None               = 0x07,
+	GestureKeyboard    = 0x08,
+}
+
+bitflags! {
+	flags Imu: u16 {
+		/// Steering mode.
+		const STEERING = 0x01,
+
+		/// Tilt mode.
+		const TILT = 0x02,
+
+		/// Send the orientation.
+		const ORIENTATION = 0x04,
+
+		/// Send the raw accelerometer values.
+		const ACCELERATION = 0x08,
+
+		/// Send the raw gyroscope values.
+		const GYROSCOPE = 0x10,
+	}
+}
+
+#[doc(hidden)]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Shadow {
+	pub lizard: bool,
+	values:     Vec<(Register, u16)>,
+}
+
+impl Default for Shadow {
+	fn default() -> Self {
+		Shadow {
+			lizard: false,
+			values: vec![
+				(Register::SleepTimeout,          360),
+				(Register::SmoothAbsoluteMouse,   0x00),
+				(Register::WirelessPacketVersion, 0x02),
+				(Register::RightTrackpadMode,     Trackpad::None as u16),
+				(Register::LeftTrackpadMode,      Trackpad::None as u16),
+				(Register::Imu,                   0x00),
+				(Register::RawJoystick,           0x00),
+			],
+		}
+	}
+}
+
+impl Shadow {
+	/// Get the value of the register.
+	pub fn get(&self, register: Register) -> Option<u16> {
+		self.values.iter().find(|&&(r, _)| r == register).map(|&(_, v)| v)
+	}
+
+	/// Set the value of the register.
+	pub fn set(&mut self, register: Register, value: u16) {
+		if let Some(entry) = self.values.iter_mut().find(|&&mut (r, _)| r == register) {
+			entry.1 = value;
+			return;
+		}
+
+		self.values.push((register, value));
+	}
+
+	/// The registers set, in the order they were first set.
+	pub fn values(&self) -> &[(Register, u16)] {
+		&self.values
+	}
+}
+
+/// Controller settings management.
+pub struct Settings<'a, T: Transport + 'a> {
+	controller: &'a mut Controller<T>,
+}
+
+impl<'a, T: Transport> Settings<'a, T> {
+	#[doc(hidden)]
+	pub fn new(controller: &'a mut Controller<T>) -> Settings<'a, T> {
+		Settings {
+			controller: controller,
+		}
+	}
+
+	#[doc(hidden)]
+	pub fn write(controller: &mut Controller<T>, values: &[(Register, u16)]) -> Res<()> {
+		for chunk in values.chunks(LIMIT) {
+			try!(controller.control_with(0x87, (chunk.len() * 3) as u8, |mut buf| {
+				for &(register, value) in chunk {
+					try!(buf.write_u8(register as u8));
+					try!(buf.write_u16::<LittleEndian>(value));
+				}
+
+				Ok(())
+			}));
+		}
+
+		Ok(())
+	}
+
+	/// Get the last value written to the register, from the shadow copy and
+	/// not from the device.
+	pub fn get(&mut self, register: Register) -> Option<u16> {
+		self.controller.shadow().get(register)
+	}
+
+	/// Write a register.
+	pub fn set(self, register: Register, value: u16) -> Res<()> {
+		self.apply(&[(register, value)])
+	}
+
+	/// Write many registers at once.
+	pub fn apply(self, values: &[(Register, u16)]) -> Res<()> {
+		try!(Settings::write(self.controller, values));
+
+		for &(register, value) in values {
+			self.controller.shadow().set(register, value);
+		}
+
+		Ok(())
+	}
+
+	/// Set the idle duration before turning off.
+	pub fn timeout(self, value: Duration) -> Res<()> {
+		if value.as_secs() > u16::max_value() as u64 {
+			return Err(Error::InvalidParameter);
+		}
+
+		self.set(Register::SleepTimeout, value.as_secs() as u16)
+	}
+
+	/// Set how long the home button has to be held to turn the controller
+	/// off.
+	pub fn home(self, value: Duration) -> Res<()> {
+		if value.as_secs() > u16::max_value() as u64 {
+			return Err(Error::InvalidParameter);
+		}
+
+		self.set(Register::HomeButtonPowerOffTime, value.as_secs() as u16)
+	}
+
+	/// Set the led brightness, between `0` and `100`.
+	pub fn led(self, value: u8) -> Res<()> {
+		if value > 100 {
+			return Err(Error::InvalidParameter);
+		}
+
+		self.set(Register::LedBrightness, value as u16)
+	}
+
+	/// Set what the gyroscope and accelerometer send.
+	pub fn imu(self, value: Imu) -> Res<()> {
+		self.set(Register::Imu, value.bits())
+	}
+
+	/// Set what the trackpads do in lizard mode.
+	pub fn trackpad(self, left: Trackpad, right: Trackpad) -> Res<()> {
+		self.apply(&[
+			(Register::LeftTrackpadMode,  left as u16),
+			(Register::RightTrackpadMode, right as u16),
+		])
+	}
+
+	/// Enable or disable smoothing of the trackpads in absolute mouse mode.
+	pub fn smoothing(self, value: bool) -> Res<()> {
+		self.set(Register::SmoothAbsoluteMouse, if value { 1 } else { 0 })
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use transport::Mock;
+	use Controller;
+	use super::{Register, Trackpad};
+
+	#[test]
+	fn shadow() {
+		let mut controller = Controller::new(Mock::new(), 0x1102).unwrap();
+		controller.transport_mut().clear();
+
+		controller.settings().led(40).unwrap();
+		controller.settings().trackpad(Trackpad::RelativeMouse, Trackpad::None).unwrap();
+		assert!(controller.settings().led(101).is_err());
+
+		assert_eq!(&[0x87, 0x03, 0x2d, 0x28, 0x00][..], &controller.transport().sent()[0][..5]);
+		assert_eq!(&[0x87, 0x06, 0x07, 0x01, 0x00, 0x08, 0x07, 0x00][..], &controller.transport().sent()[1][..8]);
+
+		assert_eq!(Some(40), controller.settings().get(Register::LedBrightness));
+		assert_eq!(Some(360), controller.settings().get(Register::SleepTimeout));
+
+		// Everything is written again on reset.
+		controller.transport_mut().clear();
+		controller.reset().unwrap();
+
+		let sent = controller.transport().sent();
+		assert_eq!(&[0x87, 0x18][..], &sent[1][..2]);
+		assert_eq!(&[0x07, 0x01, 0x00][..], &sent[1][14 .. 17]);
+		assert_eq!(&[0x2d, 0x28, 0x00][..], &sent[1][23 .. 26]);
+	}
+}
@@ -0,0 +1,135 @@
+use std::io::Read;
+use std::fs::File;
+use std::path::Path;
+use std::time::Duration;
+use Result as Res;
+use super::{Note, midi};
+
+/// A note in a voice.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Tone {
+	/// Offset from the start of the melody.
+	pub at: Duration,
+
+	/// The MIDI key.
+	pub key: u8,
+
+	/// How long the note lasts.
+	pub duration: Duration,
+}
+
+/// A sequence of notes and rests played on a single channel.
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct Voice {
+	tones:  Vec<Tone>,
+	cursor: Duration,
+}
+
+impl Voice {
+	/// Create an empty voice.
+	pub fn new() -> Voice {
+		Voice::default()
+	}
+
+	/// Add a note after the previous one.
+	pub fn note(self, note: Note, sharp: bool, octave: u8, duration: Duration) -> Self {
+		self.key(note.key(sharp, octave), duration)
+	}
+
+	/// Add a MIDI key after the previous one.
+	pub fn key(mut self, key: u8, duration: Duration) -> Self {
+		self.tones.push(Tone {
+			at:       self.cursor,
+			key:      key,
+			duration: duration,
+		});
+
+		self.cursor = self.cursor + duration;
+		self
+	}
+
+	/// Add a rest after the previous note.
+	pub fn rest(mut self, duration: Duration) -> Self {
+		self.cursor = self.cursor + duration;
+		self
+	}
+
+	/// Add a tone at its offset, it must not start before the end of the
+	/// previous one.
+	pub fn tone(mut self, tone: Tone) -> Self {
+		if tone.at >= self.cursor {
+			self.cursor = tone.at + tone.duration;
+			self.tones.push(tone);
+		}
+
+		self
+	}
+
+	/// The tones in the voice, sorted by offset.
+	pub fn tones(&self) -> &[Tone] {
+		&self.tones
+	}
+
+	/// How long the voice lasts.
+	pub fn duration(&self) -> Duration {
+		self.cursor
+	}
+}
+
+/// Two voices played on the left and right channels.
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct Melody {
+	/// The voice on the left channel.
+	pub left: Voice,
+
+	/// The voice on the right channel.
+	pub right: Voice,
+}
+
+impl Melody {
+	/// Create an empty melody.
+	pub fn new() -> Melody {
+		Melody::default()
+	}
+
+	/// The voice to play on the left channel.
+	pub fn left(mut self, voice: Voice) -> Self {
+		self.left = voice;
+		self
+	}
+
+	/// The voice to play on the right channel.
+	pub fn right(mut self, voice: Voice) -> Self {
+		self.right = voice;
+		self
+	}
+
+	/// The voice to play on both channels.
+	pub fn both(self, voice: Voice) -> Self {
+		self.left(voice.clone()).right(voice)
+	}
+
+	/// How long the melody lasts.
+	pub fn duration(&self) -> Duration {
+		if self.left.duration() > self.right.duration() {
+			self.left.duration()
+		}
+		else {
+			self.right.duration()
+		}
+	}
+
+	/// Import a Standard MIDI File.
+	///
+	/// The percussion channel is ignored and the notes of every other channel
+	/// are spread on the two voices, when more than two notes play at once
+	/// the highest ones are kept.
+	pub fn midi<R: Read>(input: R) -> Res<Melody> {
+		midi::parse(input)
+	}
+
+	/// Import a Standard MIDI File from the given path.
+	pub fn load<P: AsRef<Path>>(path: P) -> Res<Melody> {
+		Melody::midi(try!(File::open(path)))
+	}
+}
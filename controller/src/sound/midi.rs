@@ -0,0 +1,371 @@
+//! Standard MIDI File import.
+
+use std::io::{Read, Cursor};
+use std::time::Duration;
+use std::collections::HashMap;
+use byteorder::{ReadBytesExt, BigEndian};
+use {Result as Res, Error};
+use super::{Melody, Voice, Tone};
+
+/// Tempo in microseconds per quarter note when the file doesn't set one.
+const TEMPO: u64 = 500_000;
+
+/// The channel reserved to percussions.
+const PERCUSSION: u8 = 9;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Event {
+	On(u8, u8),
+	Off(u8, u8),
+	Tempo(u64),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Division {
+	/// Ticks per quarter note.
+	Metrical(u64),
+
+	/// Microseconds per tick.
+	Timecode(f64),
+}
+
+fn length(input: &mut Cursor<&[u8]>) -> Res<u64> {
+	let mut value = 0u64;
+
+	for _ in 0 .. 4 {
+		let byte = try!(input.read_u8());
+		value = (value << 7) | (byte & 0x7f) as u64;
+
+		if byte & 0x80 == 0 {
+			return Ok(value);
+		}
+	}
+
+	Err(Error::InvalidParameter)
+}
+
+fn skip(input: &mut Cursor<&[u8]>, amount: u64) -> Res<()> {
+	let position = input.position() + amount;
+
+	if position > input.get_ref().len() as u64 {
+		return Err(Error::InvalidParameter);
+	}
+
+	input.set_position(position);
+	Ok(())
+}
+
+fn track(data: &[u8]) -> Res<Vec<(u64, Event)>> {
+	let mut input   = Cursor::new(data);
+	let mut events  = Vec::new();
+	let mut tick    = 0;
+	let mut running = None;
+
+	while (input.position() as usize) < data.len() {
+		tick += try!(length(&mut input));
+
+		let mut status = try!(input.read_u8());
+
+		// Running status, the byte is the first data byte.
+		let first = if status < 0x80 {
+			let first = status;
+			status    = try!(running.ok_or(Error::InvalidParameter));
+
+			Some(first)
+		}
+		else {
+			None
+		};
+
+		match status {
+			0xff => {
+				let kind = try!(input.read_u8());
+				let size = try!(length(&mut input));
+
+				if kind == 0x2f {
+					break;
+				}
+
+				if kind == 0x51 && size == 3 {
+					let tempo = try!(input.read_uint::<BigEndian>(3));
+					events.push((tick, Event::Tempo(tempo)));
+				}
+				else {
+					try!(skip(&mut input, size));
+				}
+			}
+
+			0xf0 | 0xf7 => {
+				let size = try!(length(&mut input));
+				try!(skip(&mut input, size));
+			}
+
+			0x80 ... 0xef => {
+				running = Some(status);
+
+				let channel = status & 0x0f;
+				let first   = match first {
+					Some(byte) => byte,
+					None       => try!(input.read_u8()),
+				};
+
+				match status & 0xf0 {
+					0x80 => {
+						try!(input.read_u8());
+						events.push((tick, Event::Off(channel, first)));
+					}
+
+					0x90 => {
+						if try!(input.read_u8()) == 0 {
+							events.push((tick, Event::Off(channel, first)));
+						}
+						else {
+							events.push((tick, Event::On(channel, first)));
+						}
+					}
+
+					0xc0 | 0xd0 =>
+						(),
+
+					_ => {
+						try!(input.read_u8());
+					}
+				}
+			}
+
+			_ =>
+				return Err(Error::InvalidParameter)
+		}
+	}
+
+	Ok(events)
+}
+
+/// Converts ticks to time following the tempo changes.
+struct Clock {
+	division: Division,
+	tempo:    Vec<(u64, u64)>,
+}
+
+impl Clock {
+	fn at(&self, tick: u64) -> Duration {
+		let micros = match self.division {
+			Division::Timecode(per) =>
+				(tick as f64 * per) as u64,
+
+			Division::Metrical(ticks) => {
+				let mut micros = 0;
+				let mut last   = (0, TEMPO);
+
+				for &(at, tempo) in self.tempo.iter().take_while(|&&(at, _)| at < tick) {
+					micros += (at - last.0) * last.1 / ticks;
+					last    = (at, tempo);
+				}
+
+				micros + (tick - last.0) * last.1 / ticks
+			}
+		};
+
+		Duration::new(micros / 1_000_000, ((micros % 1_000_000) * 1_000) as u32)
+	}
+}
+
+/// Spreads the notes on the two voices.
+fn spread(mut notes: Vec<(Duration, Duration, u8)>) -> Melody {
+	notes.sort_by(|a, b| a.0.cmp(&b.0).then(b.2.cmp(&a.2)));
+
+	let mut voices = vec![Vec::<Tone>::new(), Vec::<Tone>::new()];
+
+	for (start, end, key) in notes {
+		let tone = Tone {
+			at:       start,
+			key:      key,
+			duration: end - start,
+		};
+
+		if let Some(voice) = voices.iter_mut().find(|v| v.last().map_or(true, |t| t.at + t.duration <= start)) {
+			voice.push(tone);
+			continue;
+		}
+
+		// Both voices are busy, replace the lowest note if this one is higher.
+		let lowest = if voices[0].last().unwrap().key <= voices[1].last().unwrap().key { 0 } else { 1 };
+
+		if voices[lowest].last().unwrap().key < key {
+			let cut = voices[lowest].pop().unwrap();
+
+			if cut.at < start {
+				voices[lowest].push(Tone { duration: start - cut.at, .. cut });
+			}
+
+			voices[lowest].push(tone);
+		}
+	}
+
+	let right = voices.pop().unwrap();
+	let left  = voices.pop().unwrap();
+
+	Melody::new()
+		.left(left.into_iter().fold(Voice::new(), |voice, tone| voice.tone(tone)))
+		.right(right.into_iter().fold(Voice::new(), |voice, tone| voice.tone(tone)))
+}
+
+pub fn parse<R: Read>(mut input: R) -> Res<Melody> {
+	let mut data = Vec::new();
+	try!(input.read_to_end(&mut data));
+
+	let mut input    = Cursor::new(&data[..]);
+	let mut division = None;
+	let mut events   = Vec::new();
+
+	while (input.position() as usize) + 8 <= data.len() {
+		let mut kind = [0u8; 4];
+		try!(input.read_exact(&mut kind));
+
+		let size  = try!(input.read_u32::<BigEndian>()) as usize;
+		let start = input.position() as usize;
+
+		if start + size > data.len() {
+			return Err(Error::InvalidParameter);
+		}
+
+		match &kind {
+			b"MThd" => {
+				let mut header = Cursor::new(&data[start .. start + size]);
+				let     format = try!(header.read_u16::<BigEndian>());
+				let     _      = try!(header.read_u16::<BigEndian>());
+				let     value  = try!(header.read_u16::<BigEndian>());
+
+				if format > 1 {
+					return Err(Error::NotSupported);
+				}
+
+				division = Some(if value & 0x8000 == 0 {
+					Division::Metrical(value as u64)
+				}
+				else {
+					// The frame rate is stored negated.
+					let fps = -(((value >> 8) as u8 as i8) as i16);
+					let per = value & 0xff;
+
+					if !(fps == 24 || fps == 25 || fps == 29 || fps == 30) || per == 0 {
+						return Err(Error::InvalidParameter);
+					}
+
+					Division::Timecode(1_000_000.0 / (fps as f64 * per as f64))
+				});
+			}
+
+			b"MTrk" => {
+				if division.is_none() {
+					return Err(Error::InvalidParameter);
+				}
+
+				events.extend(try!(track(&data[start .. start + size])));
+			}
+
+			_ =>
+				()
+		}
+
+		input.set_position((start + size) as u64);
+	}
+
+	let division = try!(division.ok_or(Error::InvalidParameter));
+
+	if division == Division::Metrical(0) {
+		return Err(Error::InvalidParameter);
+	}
+
+	// Tracks are merged, the sort is stable so events keep their order.
+	events.sort_by_key(|&(tick, _)| tick);
+
+	let clock = Clock {
+		division: division,
+		tempo:    events.iter().filter_map(|&(tick, event)|
+			if let Event::Tempo(tempo) = event { Some((tick, tempo)) } else { None }).collect(),
+	};
+
+	let mut pending = HashMap::<(u8, u8), Vec<u64>>::new();
+	let mut notes   = Vec::new();
+	let     last    = events.last().map(|&(tick, _)| tick).unwrap_or(0);
+
+	for &(tick, event) in &events {
+		match event {
+			Event::On(channel, key) if channel != PERCUSSION =>
+				pending.entry((channel, key)).or_insert_with(Vec::new).push(tick),
+
+			Event::Off(channel, key) if channel != PERCUSSION => {
+				if let Some(starts) = pending.get_mut(&(channel, key)) {
+					if !starts.is_empty() {
+						let start = starts.remove(0);
+						notes.push((clock.at(start), clock.at(tick), key));
+					}
+				}
+			}
+
+			_ =>
+				()
+		}
+	}
+
+	// Notes never released last until the end.
+	for ((_, key), starts) in pending {
+		for start in starts {
+			notes.push((clock.at(start), clock.at(last), key));
+		}
+	}
+
+	Ok(spread(notes.into_iter().filter(|&(start, end, _)| end > start).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+	use super::parse;
+
+	#[test]
+	fn midi() {
+		let mut file = [
+			// Header, format 0, one track, 96 ticks per quarter.
+			b'M', b'T', b'h', b'd', 0, 0, 0, 6, 0, 0, 0, 1, 0, 96,
+
+			b'M', b'T', b'r', b'k', 0, 0, 0, 35,
+			// 120 bpm.
+			0x00, 0xff, 0x51, 0x03, 0x07, 0xa1, 0x20,
+			// Three notes at once, the lowest is dropped.
+			0x00, 0x90, 60, 100,
+			0x00, 64, 100,
+			0x00, 67, 100,
+			// Percussions are ignored.
+			0x00, 0x99, 36, 100,
+			// Release everything after a quarter.
+			0x60, 0x80, 60, 0,
+			0x00, 64, 0,
+			0x00, 67, 0,
+			0x00, 0xff, 0x2f, 0x00,
+		];
+
+		let melody = parse(&file[..]).unwrap();
+
+		assert_eq!(1, melody.left.tones().len());
+		assert_eq!(67, melody.left.tones()[0].key);
+		assert_eq!(Duration::from_millis(500), melody.left.tones()[0].duration);
+
+		assert_eq!(1, melody.right.tones().len());
+		assert_eq!(64, melody.right.tones()[0].key);
+
+		// 25 frames per second, 40 ticks per frame.
+		file[12] = 0xe7;
+		file[13] = 40;
+		assert!(parse(&file[..]).is_ok());
+
+		// Invalid frame rates and zero ticks per frame.
+		file[12] = 0x80;
+		assert!(parse(&file[..]).is_err());
+
+		file[12] = 0xe7;
+		file[13] = 0;
+		assert!(parse(&file[..]).is_err());
+	}
+}
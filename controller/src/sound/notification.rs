@@ -0,0 +1,68 @@
+/// The built-in notification sounds.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Notification {
+	WarmAndHappy        = 0x00,
+	Invader             = 0x01,
+	ControllerConfirmed = 0x02,
+	Victory             = 0x03,
+	RiseAndShine        = 0x04,
+	Shorty              = 0x05,
+	WarmBoot            = 0x06,
+	NextLevel           = 0x07,
+	ShakeItOff          = 0x08,
+	AccessDenied        = 0x09,
+	Deactivate          = 0x0a,
+	Discovery           = 0x0b,
+	Triumph             = 0x0c,
+	TheMann             = 0x0d,
+}
+
+/// All the notification sounds, in ID order.
+pub const NOTIFICATIONS: [Notification; 14] = [
+	Notification::WarmAndHappy,
+	Notification::Invader,
+	Notification::ControllerConfirmed,
+	Notification::Victory,
+	Notification::RiseAndShine,
+	Notification::Shorty,
+	Notification::WarmBoot,
+	Notification::NextLevel,
+	Notification::ShakeItOff,
+	Notification::AccessDenied,
+	Notification::Deactivate,
+	Notification::Discovery,
+	Notification::Triumph,
+	Notification::TheMann,
+];
+
+impl Notification {
+	/// Get the notification sound with the given ID.
+	pub fn from_id(value: u8) -> Option<Notification> {
+		NOTIFICATIONS.get(value as usize).cloned()
+	}
+
+	/// The ID of the notification sound.
+	pub fn id(&self) -> u8 {
+		*self as u8
+	}
+
+	/// The name of the notification sound, as shown by Steam.
+	pub fn name(&self) -> &'static str {
+		match *self {
+			Notification::WarmAndHappy        => "Warm and Happy",
+			Notification::Invader             => "Invader",
+			Notification::ControllerConfirmed => "Controller Confirmed",
+			Notification::Victory             => "Victory",
+			Notification::RiseAndShine        => "Rise and Shine",
+			Notification::Shorty              => "Shorty",
+			Notification::WarmBoot            => "Warm Boot",
+			Notification::NextLevel           => "Next Level",
+			Notification::ShakeItOff          => "Shake it off",
+			Notification::AccessDenied        => "Access Denied",
+			Notification::Deactivate          => "Deactivate",
+			Notification::Discovery           => "Discovery",
+			Notification::Triumph             => "Triumph",
+			Notification::TheMann             => "The Mann",
+		}
+	}
+}
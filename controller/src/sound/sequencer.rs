@@ -0,0 +1,220 @@
+use std::thread;
+use std::time::{Duration, Instant};
+use {Result as Res, Controller, Transport};
+use super::{Melody, Tone};
+
+/// Plays a melody, it has to be ticked regularly and tells how long until
+/// the next note is due, so it can be driven by the same loop reading states.
+pub struct Sequencer {
+	melody:  Option<Melody>,
+	start:   Instant,
+	paused:  Option<Instant>,
+	next:    [usize; 2],
+	silence: bool,
+}
+
+impl Default for Sequencer {
+	fn default() -> Self {
+		Sequencer {
+			melody:  None,
+			start:   Instant::now(),
+			paused:  None,
+			next:    [0, 0],
+			silence: false,
+		}
+	}
+}
+
+impl Sequencer {
+	/// Create an idle sequencer.
+	pub fn new() -> Sequencer {
+		Sequencer::default()
+	}
+
+	/// Start playing a melody now, replacing the one playing.
+	pub fn play(&mut self, melody: Melody) {
+		self.play_at(melody, Instant::now())
+	}
+
+	/// Start playing a melody at the given instant, replacing the one playing.
+	pub fn play_at(&mut self, melody: Melody, start: Instant) {
+		self.silence = self.melody.is_some();
+		self.melody  = Some(melody);
+		self.start   = start;
+		self.paused  = None;
+		self.next    = [0, 0];
+	}
+
+	/// Pause the melody.
+	pub fn pause(&mut self) {
+		self.pause_at(Instant::now())
+	}
+
+	/// Pause the melody at the given instant.
+	pub fn pause_at(&mut self, at: Instant) {
+		if self.melody.is_some() && self.paused.is_none() {
+			self.paused  = Some(at);
+			self.silence = true;
+		}
+	}
+
+	/// Resume the melody.
+	pub fn resume(&mut self) {
+		self.resume_at(Instant::now())
+	}
+
+	/// Resume the melody at the given instant, the interrupted notes are
+	/// played again for what's left of them.
+	pub fn resume_at(&mut self, at: Instant) {
+		if let Some(paused) = self.paused.take() {
+			let elapsed = paused - self.start;
+			self.start  = at - elapsed;
+
+			if let Some(ref melody) = self.melody {
+				for (next, voice) in self.next.iter_mut().zip([&melody.left, &melody.right].iter()) {
+					if *next > 0 {
+						let tone = voice.tones()[*next - 1];
+
+						if tone.at + tone.duration > elapsed {
+							*next -= 1;
+						}
+					}
+				}
+			}
+		}
+	}
+
+	/// Stop playing.
+	pub fn stop(&mut self) {
+		self.silence = self.melody.is_some();
+		self.melody  = None;
+		self.paused  = None;
+	}
+
+	/// Check if nothing is playing.
+	pub fn is_empty(&self) -> bool {
+		self.melody.is_none()
+	}
+
+	/// Check if the melody is paused.
+	pub fn is_paused(&self) -> bool {
+		self.paused.is_some()
+	}
+
+	/// Send the notes due at the given instant, returns how long until the
+	/// next note is due, if any.
+	pub fn tick<T: Transport>(&mut self, controller: &mut Controller<T>, now: Instant) -> Res<Option<Duration>> {
+		if self.silence {
+			self.silence = false;
+
+			try!(controller.sound().left().stop());
+			try!(controller.sound().right().stop());
+		}
+
+		if self.paused.is_some() || now < self.start {
+			return Ok(if self.paused.is_some() || self.melody.is_none() {
+				None
+			}
+			else {
+				Some(self.start - now)
+			});
+		}
+
+		let elapsed = now - self.start;
+		let mut due = None;
+
+		if let Some(ref melody) = self.melody {
+			for (index, voice) in [&melody.left, &melody.right].iter().enumerate() {
+				let tones   = voice.tones();
+				let mut hit = None::<Tone>;
+
+				while let Some(&tone) = tones.get(self.next[index]) {
+					if tone.at > elapsed {
+						break;
+					}
+
+					self.next[index] += 1;
+
+					if tone.at + tone.duration > elapsed {
+						hit = Some(tone);
+					}
+				}
+
+				if let Some(tone) = hit {
+					let sound = controller.sound()
+						.key(tone.key)
+						.duration(tone.at + tone.duration - elapsed);
+
+					try!(if index == 0 { sound.left() } else { sound.right() }.play());
+				}
+
+				if let Some(tone) = tones.get(self.next[index]) {
+					let wait = tone.at - elapsed;
+					due = Some(due.map_or(wait, |d: Duration| if wait < d { wait } else { d }));
+				}
+			}
+
+			if due.is_none() && elapsed < melody.duration() {
+				due = Some(melody.duration() - elapsed);
+			}
+		}
+
+		if due.is_none() {
+			self.melody = None;
+		}
+
+		Ok(due)
+	}
+
+	/// Play until the end, blocking.
+	pub fn wait<T: Transport>(&mut self, controller: &mut Controller<T>) -> Res<()> {
+		while let Some(due) = try!(self.tick(controller, Instant::now())) {
+			thread::sleep(due);
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::{Duration, Instant};
+	use transport::Mock;
+	use Controller;
+	use sound::{Melody, Voice, Note, Sequencer};
+
+	#[test]
+	fn sequencer() {
+		let mut controller = Controller::new(Mock::new(), 0x1102).unwrap();
+		let mut sequencer  = Sequencer::new();
+		let     start      = Instant::now();
+
+		controller.transport_mut().clear();
+		sequencer.play_at(Melody::new()
+			.left(Voice::new()
+				.note(Note::C, false, 7, Duration::from_millis(100))
+				.rest(Duration::from_millis(50))
+				.note(Note::G, false, 6, Duration::from_millis(100)))
+			.right(Voice::new()
+				.rest(Duration::from_millis(100))
+				.key(96, Duration::from_millis(100))), start);
+
+		assert_eq!(Some(Duration::from_millis(100)), sequencer.tick(&mut controller, start).unwrap());
+		assert_eq!(1, controller.transport().sent().len());
+		assert_eq!(&[0x8f, 0x07, 0x01][..], &controller.transport().sent()[0][..3]);
+
+		// Pausing silences both channels and resuming plays the note again.
+		sequencer.pause_at(start + Duration::from_millis(50));
+		assert_eq!(None, sequencer.tick(&mut controller, start + Duration::from_millis(60)).unwrap());
+		assert_eq!(3, controller.transport().sent().len());
+
+		sequencer.resume_at(start + Duration::from_millis(100));
+		assert_eq!(Some(Duration::from_millis(50)), sequencer.tick(&mut controller, start + Duration::from_millis(100)).unwrap());
+		assert_eq!(4, controller.transport().sent().len());
+
+		sequencer.stop();
+		assert_eq!(None, sequencer.tick(&mut controller, start + Duration::from_millis(150)).unwrap());
+		assert_eq!(6, controller.transport().sent().len());
+		assert!(sequencer.is_empty());
+	}
+}
@@ -1,39 +1,75 @@
 use std::{u8, i16};
-use std::io::{Read, Seek, SeekFrom};
-use byteorder::{ReadBytesExt, BigEndian, LittleEndian};
+use std::io::{Read, Write, Seek, SeekFrom};
+use byteorder::{ReadBytesExt, WriteBytesExt, BigEndian, LittleEndian};
 
 use {Result as Res, Error, Button};
 
+/// Accelerometer resolution, in units per g.
+const ACCELERATION: f32 = 16384.0;
+
+/// Gyroscope resolution, in units per degree per second.
+const ROTATION: f32 = 16.0;
+
 /// The controller state.
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub enum State {
 	/// The controller is powering on or off.
 	Power(bool),
 
+	/// The controller status, sent when there's no input.
 	Idle {
 		/// Sequence number for the state.
 		sequence: u32,
+
+		/// Battery status.
+		battery: Battery,
 	},
 
 	Input {
 		/// Sequence number for the state.
 		sequence: u32,
 
-		/// Button state of the controller.
+		/// Button state of the controller, unknown buttons are dropped.
 		buttons: Button,
 
+		/// Raw button state of the controller, including unknown buttons.
+		raw: u32,
+
 		/// Trigger state of the controller.
 		trigger: Trigger,
 
 		/// Pads state.
 		pad: Pad,
 
-		/// Orientation of the controller if sensors are enabled.
-		orientation: Angles,
+		/// Acceleration of the controller in g, if sensors are enabled.
+		acceleration: Vector,
 
-		/// Acceleration of the controller if sensors are enabled.
-		acceleration: Angles,
-	}
+		/// Angular velocity of the controller in degrees per second, if
+		/// sensors are enabled.
+		rotation: Vector,
+
+		/// Orientation of the controller, if sensors are enabled.
+		orientation: Quaternion,
+	},
+
+	/// A report that isn't known yet.
+	Unknown {
+		/// The report ID.
+		id: u8,
+
+		/// The report payload.
+		payload: Vec<u8>,
+	},
+}
+
+/// The battery status, wired controllers always report it as full.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct Battery {
+	/// The voltage in millivolts.
+	pub voltage: u16,
+
+	/// The charge percentage.
+	pub percent: u8,
 }
 
 /// The pressure on the triggers of the controller.
@@ -72,22 +108,162 @@ impl Axis {
 	}
 }
 
-/// 3D position of the controller.
-#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+/// Vector along the axes of the controller.
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct Vector {
+	/// The X axis.
+	pub x: f32,
+
+	/// The Y axis.
+	pub y: f32,
+
+	/// The Z axis.
+	pub z: f32,
+}
+
+/// Orientation of the controller as an unit quaternion.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Quaternion {
+	pub w: f32,
+	pub x: f32,
+	pub y: f32,
+	pub z: f32,
+}
+
+impl Default for Quaternion {
+	fn default() -> Self {
+		Quaternion {
+			w: 1.0,
+			x: 0.0,
+			y: 0.0,
+			z: 0.0,
+		}
+	}
+}
+
+impl Quaternion {
+	/// Convert to Euler angles.
+	pub fn angles(&self) -> Angles {
+		let &Quaternion { w, x, y, z } = self;
+		let pitch = 2.0 * (w * y - z * x);
+
+		Angles {
+			roll:  (2.0 * (w * x + y * z)).atan2(1.0 - 2.0 * (x * x + y * y)).to_degrees(),
+			pitch: (if pitch > 1.0 { 1.0 } else if pitch < -1.0 { -1.0 } else { pitch }).asin().to_degrees(),
+			yaw:   (2.0 * (w * z + x * y)).atan2(1.0 - 2.0 * (y * y + z * z)).to_degrees(),
+		}
+	}
+}
+
+/// Euler angles in degrees, the roll is around the X axis, the pitch around
+/// the Y axis and the yaw around the Z axis.
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
 pub struct Angles {
 	/// The pitch.
-	pub pitch: i16,
+	pub pitch: f32,
 
 	/// The roll.
-	pub roll: i16,
+	pub roll: f32,
 
 	/// The yaw.
-	pub yaw: i16,
+	pub yaw: f32,
+}
+
+impl Angles {
+	/// Convert to a quaternion.
+	pub fn quaternion(&self) -> Quaternion {
+		let (sr, cr) = (self.roll.to_radians() / 2.0).sin_cos();
+		let (sp, cp) = (self.pitch.to_radians() / 2.0).sin_cos();
+		let (sy, cy) = (self.yaw.to_radians() / 2.0).sin_cos();
+
+		Quaternion {
+			w: cr * cp * cy + sr * sp * sy,
+			x: sr * cp * cy - cr * sp * sy,
+			y: cr * sp * cy + sr * cp * sy,
+			z: cr * cp * sy - sr * sp * cy,
+		}
+	}
 }
 
 impl State {
-	/// Parse the state from a given packet.
+	/// Parse the state from a given packet, on failure the error carries the
+	/// report ID and the offset where parsing stopped.
 	pub fn parse<R: Read + Seek>(id: u8, mut buffer: R) -> Res<State> {
+		let start = try!(buffer.seek(SeekFrom::Current(0)));
+
+		State::read(id, &mut buffer).map_err(|err| match err {
+			Error::Io(..) | Error::InvalidParameter => Error::Parse {
+				id:     id,
+				offset: buffer.seek(SeekFrom::Current(0)).unwrap_or(start) - start,
+			},
+
+			err =>
+				err
+		})
+	}
+
+	/// The report ID of the state.
+	pub fn id(&self) -> u8 {
+		match self {
+			&State::Input { .. }       => 0x01,
+			&State::Power(..)          => 0x03,
+			&State::Idle { .. }        => 0x04,
+			&State::Unknown { id, .. } => id,
+		}
+	}
+
+	/// Write the payload of the report, the inverse of `parse`.
+	pub fn write<W: Write>(&self, mut buffer: W) -> Res<()> {
+		match self {
+			&State::Input { sequence, raw, trigger, pad, acceleration, rotation, orientation, .. } => {
+				let precise = |value: f32| (value.max(0.0).min(1.0) * i16::max_value() as f32) as u16;
+				let coarse  = |value: f32| (value.max(0.0).min(1.0) * u8::max_value() as f32) as u8;
+
+				try!(buffer.write_u32::<LittleEndian>(sequence));
+				try!(buffer.write_u32::<BigEndian>((raw << 8) | coarse(trigger.left) as u32));
+				try!(buffer.write_u8(coarse(trigger.right)));
+				try!(buffer.write_all(&[0; 3]));
+
+				try!(buffer.write_i16::<LittleEndian>(pad.left.x));
+				try!(buffer.write_i16::<LittleEndian>(pad.left.y));
+				try!(buffer.write_i16::<LittleEndian>(pad.right.x));
+				try!(buffer.write_i16::<LittleEndian>(pad.right.y));
+
+				try!(buffer.write_u16::<LittleEndian>(precise(trigger.left)));
+				try!(buffer.write_u16::<LittleEndian>(precise(trigger.right)));
+
+				let sensors = [
+					acceleration.x * ACCELERATION, acceleration.y * ACCELERATION, acceleration.z * ACCELERATION,
+					rotation.x * ROTATION, rotation.y * ROTATION, rotation.z * ROTATION,
+					orientation.w * i16::max_value() as f32, orientation.x * i16::max_value() as f32,
+					orientation.y * i16::max_value() as f32, orientation.z * i16::max_value() as f32,
+				];
+
+				for &value in &sensors {
+					try!(buffer.write_i16::<LittleEndian>(value.round() as i16));
+				}
+			}
+
+			&State::Power(on) => {
+				try!(buffer.write_u8(if on { 0x02 } else { 0x01 }));
+			}
+
+			&State::Idle { sequence, battery } => {
+				try!(buffer.write_u32::<LittleEndian>(sequence));
+				try!(buffer.write_all(&[0; 4]));
+				try!(buffer.write_u16::<LittleEndian>(battery.voltage));
+				try!(buffer.write_u8(battery.percent));
+			}
+
+			&State::Unknown { ref payload, .. } => {
+				try!(buffer.write_all(payload));
+			}
+		}
+
+		Ok(())
+	}
+
+	fn read<R: Read + Seek>(id: u8, mut buffer: R) -> Res<State> {
 		match id {
 			0x01 => {
 				let sequence = try!(buffer.read_u32::<LittleEndian>());
@@ -107,20 +283,16 @@ impl State {
 				let ltrigp = try!(buffer.read_u16::<LittleEndian>());
 				let rtrigp = try!(buffer.read_u16::<LittleEndian>());
 
-				try!(buffer.seek(SeekFrom::Current(8)));
-
-				let apitch = try!(buffer.read_i16::<LittleEndian>());
-				let ayaw   = try!(buffer.read_i16::<LittleEndian>());
-				let aroll  = try!(buffer.read_i16::<LittleEndian>());
-
-				let opitch = try!(buffer.read_i16::<LittleEndian>());
-				let oyaw   = try!(buffer.read_i16::<LittleEndian>());
-				let oroll  = try!(buffer.read_i16::<LittleEndian>());
+				let mut sensors = [0i16; 10];
+				for value in sensors.iter_mut() {
+					*value = try!(buffer.read_i16::<LittleEndian>());
+				}
 
 				Ok(State::Input {
 					sequence: sequence,
 
-					buttons: try!(Button::from_bits(buttons).ok_or(Error::InvalidParameter)),
+					buttons: Button::from_bits_truncate(buttons),
+					raw:     buttons,
 
 					trigger: Trigger {
 						left: if ltrigp != 0 {
@@ -150,17 +322,30 @@ impl State {
 						},
 					},
 
-					orientation: Angles {
-						roll:  oroll,
-						pitch: opitch,
-						yaw:   oyaw,
+					acceleration: Vector {
+						x: sensors[0] as f32 / ACCELERATION,
+						y: sensors[1] as f32 / ACCELERATION,
+						z: sensors[2] as f32 / ACCELERATION,
 					},
 
-					acceleration: Angles {
-						roll:  aroll,
-						pitch: apitch,
-						yaw:   ayaw,
+					rotation: Vector {
+						x: sensors[3] as f32 / ROTATION,
+						y: sensors[4] as f32 / ROTATION,
+						z: sensors[5] as f32 / ROTATION,
+					},
+
+					// The quaternion is all zeroes when sensors are disabled.
+					orientation: if sensors[6 ..].iter().all(|&v| v == 0) {
+						Quaternion::default()
 					}
+					else {
+						Quaternion {
+							w: sensors[6] as f32 / i16::max_value() as f32,
+							x: sensors[7] as f32 / i16::max_value() as f32,
+							y: sensors[8] as f32 / i16::max_value() as f32,
+							z: sensors[9] as f32 / i16::max_value() as f32,
+						}
+					},
 				})
 			}
 
@@ -179,13 +364,73 @@ impl State {
 			0x04 => {
 				let sequence = try!(buffer.read_u32::<LittleEndian>());
 
+				try!(buffer.seek(SeekFrom::Current(4)));
+
+				let voltage = try!(buffer.read_u16::<LittleEndian>());
+				let percent = try!(buffer.read_u8());
+
 				Ok(State::Idle {
 					sequence: sequence,
+
+					battery: Battery {
+						voltage: voltage,
+						percent: percent,
+					},
 				})
 			}
 
-			_ =>
-				Err(Error::InvalidParameter)
+			_ => {
+				let mut payload = Vec::new();
+				try!(buffer.read_to_end(&mut payload));
+
+				Ok(State::Unknown {
+					id:      id,
+					payload: payload,
+				})
+			}
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use std::io::Cursor;
+	use byteorder::{WriteBytesExt, LittleEndian};
+	use Error;
+	use super::{State, Angles};
+
+	#[test]
+	fn sensors() {
+		let mut packet = vec![0u8; 24];
+		for &value in &[16384i16, 0, -8192, 160, 0, 0, 23170, 0, 0, 23170] {
+			packet.write_i16::<LittleEndian>(value).unwrap();
+		}
+		packet.resize(60, 0);
+
+		match State::parse(0x01, Cursor::new(&packet[..])).unwrap() {
+			State::Input { acceleration, rotation, orientation, .. } => {
+				assert_eq!(1.0, acceleration.x);
+				assert_eq!(-0.5, acceleration.z);
+				assert_eq!(10.0, rotation.x);
+
+				let angles = orientation.angles();
+				assert!((angles.yaw - 90.0).abs() < 0.01);
+				assert!(angles.roll.abs() < 0.01 && angles.pitch.abs() < 0.01);
+			}
+
+			_ => panic!()
+		}
+
+		assert_eq!(State::Unknown { id: 0x42, payload: vec![1, 2] }, State::parse(0x42, Cursor::new(&[1, 2][..])).unwrap());
+
+		match State::parse(0x04, Cursor::new(&[0x2a, 0, 0, 0, 0, 0][..])) {
+			Err(Error::Parse { id: 0x04, offset: 6 }) => (),
+			_ => panic!()
+		}
+
+		let angles = Angles { pitch: 10.0, roll: 20.0, yaw: 30.0 }.quaternion().angles();
+		assert!((angles.pitch - 10.0).abs() < 0.01);
+		assert!((angles.roll - 20.0).abs() < 0.01);
+		assert!((angles.yaw - 30.0).abs() < 0.01);
+	}
+}
@@ -0,0 +1,399 @@
+use std::io;
+use std::time::Duration;
+use byteorder::{WriteBytesExt, LittleEndian};
+use {Result as Res, Error, State, Button, Battery, Axis, Trigger, Pad, Vector, Quaternion};
+use button::{PAD_TOUCH, TRACK_TOUCH};
+use super::{Transport, Mock};
+
+/// The firmware build reported in the details.
+const BUILD: u32 = 1462060800;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Step {
+	Press(Button),
+	Release(Button),
+	Stick(Axis),
+	LeftPad(Option<Axis>),
+	RightPad(Option<Axis>),
+	LeftTrigger(f32),
+	RightTrigger(f32),
+	Acceleration(Vector),
+	Rotation(Vector),
+	Orientation(Quaternion),
+	Idle(Battery),
+	Power(bool),
+}
+
+/// Emulated wired controller.
+///
+/// The script is played back as the reports a real controller would send,
+/// one report for every step, without waiting; requests are answered and
+/// recorded like `Mock` does.
+///
+/// ```
+/// # use std::time::Duration;
+/// # use steamy_controller::{Controller, button};
+/// # use steamy_controller::transport::Emulator;
+/// let emulator = Emulator::new()
+/// 	.at(Duration::from_millis(10)).press(button::A)
+/// 	.at(Duration::from_millis(20)).left_pad(1000, -500)
+/// 	.at(Duration::from_millis(30)).right_trigger(0.5);
+///
+/// let mut controller = Controller::new(emulator, 0x1102).unwrap();
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub struct Emulator {
+	mock:   Mock,
+	steps:  Vec<(Duration, Step)>,
+	next:   usize,
+	cursor: Duration,
+	now:    Duration,
+	state:  State,
+}
+
+impl Default for Emulator {
+	fn default() -> Self {
+		Emulator {
+			mock:   Mock::new(),
+			steps:  Vec::new(),
+			next:   0,
+			cursor: Duration::from_secs(0),
+			now:    Duration::from_secs(0),
+			state:  State::Input {
+				sequence: 0,
+
+				buttons: Button::empty(),
+				raw:     0,
+
+				trigger:      Trigger::default(),
+				pad:          Pad::default(),
+				acceleration: Vector::default(),
+				rotation:     Vector::default(),
+				orientation:  Quaternion::default(),
+			},
+		}
+	}
+}
+
+impl Emulator {
+	/// Create an emulator with an empty script.
+	pub fn new() -> Emulator {
+		Emulator::default()
+	}
+
+	fn step(mut self, step: Step) -> Self {
+		let at    = self.cursor;
+		let index = self.steps.iter().position(|&(t, _)| t > at).unwrap_or(self.steps.len());
+
+		self.steps.insert(index, (at, step));
+		self
+	}
+
+	/// The following steps happen at the given time since the start.
+	pub fn at(mut self, value: Duration) -> Self {
+		self.cursor = value;
+		self
+	}
+
+	/// The following steps happen after the given time.
+	pub fn after(mut self, value: Duration) -> Self {
+		self.cursor = self.cursor + value;
+		self
+	}
+
+	/// Press the buttons.
+	pub fn press(self, value: Button) -> Self {
+		self.step(Step::Press(value))
+	}
+
+	/// Release the buttons.
+	pub fn release(self, value: Button) -> Self {
+		self.step(Step::Release(value))
+	}
+
+	/// Move the analog stick.
+	pub fn stick(self, x: i16, y: i16) -> Self {
+		self.step(Step::Stick(Axis { x: x, y: y }))
+	}
+
+	/// Touch the left pad.
+	pub fn left_pad(self, x: i16, y: i16) -> Self {
+		self.step(Step::LeftPad(Some(Axis { x: x, y: y })))
+	}
+
+	/// Stop touching the left pad.
+	pub fn lift_left(self) -> Self {
+		self.step(Step::LeftPad(None))
+	}
+
+	/// Touch the right pad.
+	pub fn right_pad(self, x: i16, y: i16) -> Self {
+		self.step(Step::RightPad(Some(Axis { x: x, y: y })))
+	}
+
+	/// Stop touching the right pad.
+	pub fn lift_right(self) -> Self {
+		self.step(Step::RightPad(None))
+	}
+
+	/// Pull the left trigger, between `0.0` and `1.0`.
+	pub fn left_trigger(self, value: f32) -> Self {
+		self.step(Step::LeftTrigger(value))
+	}
+
+	/// Pull the right trigger, between `0.0` and `1.0`.
+	pub fn right_trigger(self, value: f32) -> Self {
+		self.step(Step::RightTrigger(value))
+	}
+
+	/// Set the acceleration.
+	pub fn acceleration(self, value: Vector) -> Self {
+		self.step(Step::Acceleration(value))
+	}
+
+	/// Set the angular velocity.
+	pub fn rotation(self, value: Vector) -> Self {
+		self.step(Step::Rotation(value))
+	}
+
+	/// Set the orientation.
+	pub fn orientation(self, value: Quaternion) -> Self {
+		self.step(Step::Orientation(value))
+	}
+
+	/// Send an idle report.
+	pub fn idle(self, battery: Battery) -> Self {
+		self.step(Step::Idle(battery))
+	}
+
+	/// Send a power report.
+	pub fn power(self, value: bool) -> Self {
+		self.step(Step::Power(value))
+	}
+
+	/// The time of the last report sent.
+	pub fn now(&self) -> Duration {
+		self.now
+	}
+
+	/// Check if the whole script has been sent.
+	pub fn is_done(&self) -> bool {
+		self.next >= self.steps.len()
+	}
+
+	/// The feature reports sent so far.
+	pub fn sent(&self) -> &[Vec<u8>] {
+		self.mock.sent()
+	}
+
+	/// Forget the feature reports sent so far.
+	pub fn clear(&mut self) {
+		self.mock.clear()
+	}
+
+	/// Apply the step, returning the report to send.
+	fn apply(&mut self, step: Step) -> State {
+		let (sequence, buttons, trigger, pad, acceleration, rotation, orientation) = match self.state {
+			State::Input { ref mut sequence, ref mut buttons, ref mut trigger, ref mut pad, ref mut acceleration, ref mut rotation, ref mut orientation, .. } =>
+				(sequence, buttons, trigger, pad, acceleration, rotation, orientation),
+
+			_ =>
+				unreachable!()
+		};
+
+		*sequence += 1;
+
+		match step {
+			Step::Press(value) =>
+				buttons.insert(value),
+
+			Step::Release(value) =>
+				buttons.remove(value),
+
+			Step::Stick(value) =>
+				pad.left = value,
+
+			Step::LeftPad(Some(value)) => {
+				buttons.insert(PAD_TOUCH);
+				pad.left = value;
+			}
+
+			Step::LeftPad(None) => {
+				buttons.remove(PAD_TOUCH);
+				pad.left = Axis::default();
+			}
+
+			Step::RightPad(Some(value)) => {
+				buttons.insert(TRACK_TOUCH);
+				pad.right = value;
+			}
+
+			Step::RightPad(None) => {
+				buttons.remove(TRACK_TOUCH);
+				pad.right = Axis::default();
+			}
+
+			Step::LeftTrigger(value) =>
+				trigger.left = value,
+
+			Step::RightTrigger(value) =>
+				trigger.right = value,
+
+			Step::Acceleration(value) =>
+				*acceleration = value,
+
+			Step::Rotation(value) =>
+				*rotation = value,
+
+			Step::Orientation(value) =>
+				*orientation = value,
+
+			Step::Idle(battery) =>
+				return State::Idle { sequence: *sequence, battery: battery },
+
+			Step::Power(value) =>
+				return State::Power(value),
+		}
+
+		State::Input {
+			sequence: *sequence,
+
+			buttons: *buttons,
+			raw:     buttons.bits(),
+
+			trigger:      *trigger,
+			pad:          *pad,
+			acceleration: *acceleration,
+			rotation:     *rotation,
+			orientation:  *orientation,
+		}
+	}
+}
+
+impl Transport for Emulator {
+	fn send_feature(&mut self, packet: &[u8]) -> Res<()> {
+		try!(self.mock.send_feature(packet));
+
+		match packet[0] {
+			// Connection status.
+			0xb4 => {
+				self.mock.reply(0xb4, &[0x02]);
+			}
+
+			// Build details.
+			0x83 => {
+				let mut payload = Vec::new();
+
+				for &(key, value) in &[(0x01, 0x1102), (0x04, BUILD), (0x05, BUILD), (0x09, 1), (0x0a, BUILD)] {
+					try!(payload.write_u8(key));
+					try!(payload.write_u32::<LittleEndian>(value));
+				}
+
+				self.mock.reply(0x83, &payload);
+			}
+
+			// Serial numbers.
+			0xae => {
+				let mut payload = vec![0x00];
+				payload.extend_from_slice(if packet[2] == 0x00 { b"EMULATED00" } else { b"EMULATED01" });
+
+				self.mock.reply(0xae, &payload);
+			}
+
+			_ =>
+				()
+		}
+
+		Ok(())
+	}
+
+	fn get_feature(&mut self, packet: &mut [u8]) -> Res<()> {
+		self.mock.get_feature(packet)
+	}
+
+	fn read_interrupt(&mut self, packet: &mut [u8], _timeout: Duration) -> Res<usize> {
+		let (at, step) = match self.steps.get(self.next) {
+			Some(&step) =>
+				step,
+
+			None =>
+				return Err(io::Error::new(io::ErrorKind::TimedOut, "the script is over").into()),
+		};
+
+		self.next += 1;
+		self.now   = at;
+
+		let state = self.apply(step);
+
+		for byte in packet.iter_mut() {
+			*byte = 0;
+		}
+
+		let size = {
+			let mut payload = io::Cursor::new(&mut packet[4 ..]);
+			try!(state.write(&mut payload));
+			payload.position()
+		};
+
+		if size > 60 {
+			return Err(Error::InvalidParameter);
+		}
+
+		packet[0] = 0x01;
+		packet[2] = state.id();
+		packet[3] = size as u8;
+
+		Ok(packet.len())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+	use {Controller, State, Axis, button};
+	use super::{Emulator, BUILD};
+
+	#[test]
+	fn script() {
+		let emulator = Emulator::new()
+			.at(Duration::from_millis(30)).right_trigger(0.5)
+			.at(Duration::from_millis(10)).press(button::A)
+			.at(Duration::from_millis(20)).left_pad(1000, -500)
+			.at(Duration::from_millis(40)).release(button::A).lift_left()
+			.after(Duration::from_millis(10)).power(false);
+
+		let mut controller = Controller::new(emulator, 0x1102).unwrap();
+		let mut states     = Vec::new();
+
+		while let Ok(state) = controller.state(Duration::from_secs(0)) {
+			states.push(state);
+		}
+
+		assert_eq!(6, states.len());
+		assert_eq!(Duration::from_millis(50), controller.transport().now());
+		assert_eq!(State::Power(false), states[5]);
+
+		match states[2] {
+			State::Input { buttons, trigger, pad, .. } => {
+				assert_eq!(button::A | button::PAD_TOUCH, buttons);
+				assert!((trigger.right - 0.5).abs() < 0.001);
+				assert_eq!(Axis { x: 1000, y: -500 }, pad.left);
+			}
+
+			_ => panic!()
+		}
+
+		match states[4] {
+			State::Input { buttons, pad, .. } => {
+				assert!(buttons.is_empty());
+				assert!(pad.left.is_empty());
+			}
+
+			_ => panic!()
+		}
+
+		assert!(controller.is_connected());
+		assert_eq!("EMULATED01", controller.details().unwrap().serial.controller);
+		assert!(controller.details().unwrap().build.is_at_least(BUILD));
+	}
+}
@@ -0,0 +1,58 @@
+use std::io;
+use std::time::Duration;
+use hid;
+use {Result as Res, Error};
+use super::Transport;
+
+/// HID transport through hidapi.
+///
+/// hidapi can't time out feature reports, so the timeout of the request
+/// policy is ignored and only its retries apply.
+pub struct Hid {
+	handle: hid::Handle,
+	packet: [u8; 65],
+}
+
+impl Hid {
+	#[doc(hidden)]
+	pub fn new(handle: hid::Handle) -> Res<Hid> {
+		Ok(Hid {
+			handle: handle,
+			packet: [0u8; 65],
+		})
+	}
+}
+
+impl Transport for Hid {
+	fn send_feature(&mut self, packet: &[u8]) -> Res<()> {
+		// The first byte is the report ID, which is always zero.
+		self.packet[0] = 0;
+		self.packet[1..].clone_from_slice(packet);
+
+		try!(self.handle.feature().send(&self.packet[..]));
+
+		Ok(())
+	}
+
+	fn get_feature(&mut self, packet: &mut [u8]) -> Res<()> {
+		try!(self.handle.feature().get(&mut self.packet[..]));
+		packet.clone_from_slice(&self.packet[1..]);
+
+		Ok(())
+	}
+
+	fn read_interrupt(&mut self, packet: &mut [u8], timeout: Duration) -> Res<usize> {
+		// Timeouts aren't errors for hidapi, a failed read means the device is
+		// gone.
+		match self.handle.data().read(packet, timeout) {
+			Ok(Some(size)) =>
+				Ok(size),
+
+			Ok(None) =>
+				Err(io::Error::new(io::ErrorKind::TimedOut, "read timed out").into()),
+
+			Err(_) =>
+				Err(Error::Disconnected),
+		}
+	}
+}
@@ -0,0 +1,85 @@
+use std::io;
+use std::time::Duration;
+use std::collections::VecDeque;
+use {Result as Res, Error};
+use super::Transport;
+
+/// In-memory transport, replies are scripted and sent feature reports are
+/// recorded.
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct Mock {
+	replies: VecDeque<Vec<u8>>,
+	input:   VecDeque<Vec<u8>>,
+	sent:    Vec<Vec<u8>>,
+}
+
+impl Mock {
+	/// Create an empty mock transport.
+	pub fn new() -> Mock {
+		Mock::default()
+	}
+
+	/// Queue a feature report reply.
+	pub fn reply(&mut self, id: u8, payload: &[u8]) -> &mut Self {
+		let mut packet = vec![0u8; 64];
+		packet[0] = id;
+		packet[1] = payload.len() as u8;
+		packet[2 .. payload.len() + 2].clone_from_slice(payload);
+
+		self.replies.push_back(packet);
+		self
+	}
+
+	/// Queue an interrupt report.
+	pub fn input(&mut self, id: u8, payload: &[u8]) -> &mut Self {
+		let mut packet = vec![0u8; 64];
+		packet[0] = 0x01;
+		packet[1] = 0x00;
+		packet[2] = id;
+		packet[3] = payload.len() as u8;
+		packet[4 .. payload.len() + 4].clone_from_slice(payload);
+
+		self.input.push_back(packet);
+		self
+	}
+
+	/// The feature reports sent so far.
+	pub fn sent(&self) -> &[Vec<u8>] {
+		&self.sent
+	}
+
+	/// Forget the feature reports sent so far.
+	pub fn clear(&mut self) {
+		self.sent.clear();
+	}
+}
+
+impl Transport for Mock {
+	fn send_feature(&mut self, packet: &[u8]) -> Res<()> {
+		self.sent.push(packet.to_vec());
+
+		Ok(())
+	}
+
+	fn get_feature(&mut self, packet: &mut [u8]) -> Res<()> {
+		if let Some(reply) = self.replies.pop_front() {
+			packet.clone_from_slice(&reply[..]);
+
+			Ok(())
+		}
+		else {
+			Err(Error::NotSupported)
+		}
+	}
+
+	fn read_interrupt(&mut self, packet: &mut [u8], _timeout: Duration) -> Res<usize> {
+		if let Some(input) = self.input.pop_front() {
+			packet.clone_from_slice(&input[..]);
+
+			Ok(packet.len())
+		}
+		else {
+			Err(io::Error::new(io::ErrorKind::TimedOut, "no more input").into())
+		}
+	}
+}
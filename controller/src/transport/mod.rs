@@ -0,0 +1,40 @@
+use std::time::Duration;
+use {Result as Res};
+
+/// The channel the controller talks over.
+///
+/// All packets are 64 bytes long, feature reports start with the request ID
+/// and size, interrupt reports start with the `0x0100` header.
+pub trait Transport {
+	/// Send a feature report.
+	fn send_feature(&mut self, packet: &[u8]) -> Res<()>;
+
+	/// Get a feature report.
+	fn get_feature(&mut self, packet: &mut [u8]) -> Res<()>;
+
+	/// Read an interrupt report, returning the number of bytes read.
+	fn read_interrupt(&mut self, packet: &mut [u8], timeout: Duration) -> Res<usize>;
+
+	/// Set the timeout for feature reports, zero waits forever; transports
+	/// that can't time out feature reports ignore it.
+	fn timeout(&mut self, _value: Duration) { }
+
+	/// Set whether the device is given back to the kernel when dropped.
+	fn restore(&mut self, _value: bool) { }
+}
+
+#[cfg(target_os = "linux")]
+mod usb;
+#[cfg(target_os = "linux")]
+pub use self::usb::Usb;
+
+#[cfg(not(target_os = "linux"))]
+mod hid;
+#[cfg(not(target_os = "linux"))]
+pub use self::hid::Hid;
+
+mod mock;
+pub use self::mock::Mock;
+
+mod emulator;
+pub use self::emulator::Emulator;
@@ -0,0 +1,108 @@
+use std::time::Duration;
+use usb;
+use {Result as Res};
+use super::Transport;
+
+/// USB transport through libusb.
+pub struct Usb<'a> {
+	handle:  usb::DeviceHandle<'a>,
+	address: u8,
+	index:   u16,
+	timeout: Duration,
+
+	claimed:  Vec<u8>,
+	detached: Vec<u8>,
+	restore:  bool,
+}
+
+impl<'a> Usb<'a> {
+	#[doc(hidden)]
+	pub fn new(device: usb::Device<'a>, mut handle: usb::DeviceHandle<'a>, endpoint: u8, index: u16) -> Res<Usb<'a>> {
+		let mut address: Option<u8> = None;
+		let mut claimed             = Vec::new();
+		let mut detached            = Vec::new();
+
+		for i in 0 .. try!(device.device_descriptor()).num_configurations() {
+			for interface in try!(device.config_descriptor(i)).interfaces() {
+				// Only touch our own interface, the wireless receiver has one for
+				// each controller and they may be opened separately or left to
+				// the kernel.
+				if interface.number() as u16 != index {
+					continue;
+				}
+
+				if try!(handle.kernel_driver_active(interface.number())) {
+					try!(handle.detach_kernel_driver(interface.number()));
+					detached.push(interface.number());
+				}
+
+				for descriptor in interface.descriptors() {
+					if descriptor.class_code() == 3 &&
+					   descriptor.sub_class_code() == 0 &&
+					   descriptor.protocol_code() == 0
+					{
+						try!(handle.claim_interface(descriptor.interface_number()));
+						claimed.push(descriptor.interface_number());
+					}
+
+					for end in descriptor.endpoint_descriptors() {
+						if end.number() == endpoint {
+							address = Some(end.address());
+						}
+					}
+				}
+			}
+		}
+
+		Ok(Usb {
+			handle:  handle,
+			address: try!(address.ok_or(usb::Error::InvalidParam)),
+			index:   index,
+			timeout: Duration::from_secs(0),
+
+			claimed:  claimed,
+			detached: detached,
+			restore:  true,
+		})
+	}
+}
+
+impl<'a> Transport for Usb<'a> {
+	fn send_feature(&mut self, packet: &[u8]) -> Res<()> {
+		try!(self.handle.write_control(0x21, 0x09, 0x0300, self.index, packet, self.timeout));
+
+		Ok(())
+	}
+
+	fn get_feature(&mut self, packet: &mut [u8]) -> Res<()> {
+		try!(self.handle.read_control(0xa1, 0x01, 0x0300, self.index, packet, self.timeout));
+
+		Ok(())
+	}
+
+	fn read_interrupt(&mut self, packet: &mut [u8], timeout: Duration) -> Res<usize> {
+		Ok(try!(self.handle.read_interrupt(self.address, packet, timeout)))
+	}
+
+	fn timeout(&mut self, value: Duration) {
+		self.timeout = value;
+	}
+
+	fn restore(&mut self, value: bool) {
+		self.restore = value;
+	}
+}
+
+impl<'a> Drop for Usb<'a> {
+	fn drop(&mut self) {
+		for &number in &self.claimed {
+			let _ = self.handle.release_interface(number);
+		}
+
+		if self.restore {
+			for &number in &self.detached {
+				let _ = self.handle.attach_kernel_driver(number);
+			}
+		}
+	}
+}
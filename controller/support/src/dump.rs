@@ -2,6 +2,7 @@ extern crate clap;
 use clap::{Arg, App};
 
 extern crate steamy_controller as controller;
+use controller::{Controller, Transport, capture};
 
 extern crate rorschach;
 use rorschach::{Definition, Field, Formatter, formatter, LittleEndian};
@@ -9,6 +10,8 @@ use rorschach::formatter::Color;
 
 use std::io::{self, Read, Write};
 use std::time::Duration;
+use std::fs::File;
+use std::process;
 
 enum Format {
 	Structured(formatter::Structured),
@@ -44,15 +47,24 @@ fn main() {
 				.short("c")
 				.long("color")
 				.help("Enable colored output."))
+			.arg(Arg::with_name("record")
+				.short("r")
+				.long("record")
+				.takes_value(true)
+				.help("Record the packets to the given capture file."))
+			.arg(Arg::with_name("replay")
+				.short("R")
+				.long("replay")
+				.takes_value(true)
+				.conflicts_with("sensors")
+				.help("Replay the packets from the given capture file."))
+			.arg(Arg::with_name("speed")
+				.long("speed")
+				.takes_value(true)
+				.requires("replay")
+				.help("Replay speed multiplier, 0 to replay without waiting."))
 		.get_matches();
 
-	let mut manager    = controller::Manager::new().unwrap();
-	let mut controller = manager.open().unwrap();
-
-	if matches.is_present("sensors") {
-		controller.sensors().on().unwrap();
-	}
-
 	let power = Definition::default()
 		.field(Field::named("event")
 			.bytes(1));
@@ -100,26 +112,36 @@ fn main() {
 		.field(Field::named("trigger.right.precise")
 			.is::<u16>(LittleEndian)
 			.style(Color::Fixed(255).on(Color::Fixed(63))))
-		.field(Field::padding()
-			.bytes(8))
-		.field(Field::named("acceleration.pitch")
+		.field(Field::named("acceleration.x")
 			.is::<i16>(LittleEndian)
 			.style(Color::Fixed(124).normal()))
-		.field(Field::named("acceleration.yaw")
+		.field(Field::named("acceleration.y")
 			.is::<i16>(LittleEndian)
 			.style(Color::Fixed(160).normal()))
-		.field(Field::named("acceleration.roll")
+		.field(Field::named("acceleration.z")
 			.is::<i16>(LittleEndian)
 			.style(Color::Fixed(196).normal()))
-		.field(Field::named("orientation.pitch")
+		.field(Field::named("rotation.x")
+			.is::<i16>(LittleEndian)
+			.style(Color::Fixed(22).normal()))
+		.field(Field::named("rotation.y")
+			.is::<i16>(LittleEndian)
+			.style(Color::Fixed(28).normal()))
+		.field(Field::named("rotation.z")
+			.is::<i16>(LittleEndian)
+			.style(Color::Fixed(34).normal()))
+		.field(Field::named("orientation.w")
 			.is::<i16>(LittleEndian)
 			.style(Color::Fixed(57).normal()))
-		.field(Field::named("orientation.yaw")
+		.field(Field::named("orientation.x")
 			.is::<i16>(LittleEndian)
 			.style(Color::Fixed(93).normal()))
-		.field(Field::named("orientation.roll")
+		.field(Field::named("orientation.y")
 			.is::<i16>(LittleEndian)
 			.style(Color::Fixed(129).normal()))
+		.field(Field::named("orientation.z")
+			.is::<i16>(LittleEndian)
+			.style(Color::Fixed(165).normal()))
 		.field(Field::padding()
 			.bytes(16));
 
@@ -144,18 +166,56 @@ fn main() {
 		Format::Inline(fmt)
 	};
 
+	let record = matches.value_of("record").map(|path|
+		capture::create(path).unwrap());
+
+	if let Some(path) = matches.value_of("replay") {
+		let speed  = matches.value_of("speed").map(|v| v.parse::<f64>().unwrap()).unwrap_or(1.0);
+		let replay = capture::Replay::new(capture::open(path).unwrap()).speed(speed);
+
+		dump(Controller::new(replay, 0x1102).unwrap(), &fmt, &power, &idle, &input, record);
+	}
+	else {
+		let mut manager    = controller::Manager::new().unwrap();
+		let mut controller = manager.open().unwrap();
+
+		if matches.is_present("sensors") {
+			controller.sensors().on().unwrap();
+		}
+
+		dump(controller, &fmt, &power, &idle, &input, record);
+	}
+}
+
+fn dump<T: Transport>(mut controller: Controller<T>, fmt: &Format, power: &Definition, idle: &Definition, input: &Definition, mut record: Option<capture::Writer<File>>) {
 	loop {
-		let (id, buffer) = controller.receive(Duration::from_secs(0)).unwrap();
+		let (id, buffer) = match controller.receive(Duration::from_secs(0)) {
+			Ok(value) =>
+				value,
+
+			// The replayed capture is over.
+			Err(controller::Error::Io(ref err)) if err.kind() == io::ErrorKind::UnexpectedEof =>
+				break,
+
+			Err(err) => {
+				writeln!(io::stderr(), "dump: {}", err).unwrap();
+				process::exit(1);
+			}
+		};
+
+		if let Some(record) = record.as_mut() {
+			record.write(id, buffer).unwrap();
+		}
 
 		match id {
 			0x03 =>
-				fmt.format(&power, buffer, io::stdout()).unwrap(),
+				fmt.format(power, buffer, io::stdout()).unwrap(),
 
 			0x04 =>
-				fmt.format(&idle, buffer, io::stdout()).unwrap(),
+				fmt.format(idle, buffer, io::stdout()).unwrap(),
 
 			0x01 =>
-				fmt.format(&input, buffer, io::stdout()).unwrap(),
+				fmt.format(input, buffer, io::stdout()).unwrap(),
 
 			_ => ()
 		}
@@ -23,8 +23,12 @@ fn main() {
 				.short("c")
 				.long("color")
 				.help("Enable colored output."))
+			.arg(Arg::with_name("details")
+				.short("d")
+				.long("details")
+				.help("Print the controller details."))
 			.arg(Arg::with_name("ID")
-				.required(true)
+				.required_unless("details")
 				.index(1)
 				.help("The request ID."))
 		.get_matches();
@@ -32,6 +36,11 @@ fn main() {
 	let mut manager    = controller::Manager::new().unwrap();
 	let mut controller = manager.open().unwrap();
 
+	if matches.is_present("details") {
+		print!("{}", controller.details().unwrap());
+		return;
+	}
+
 	let id  = u8::from_str_radix(matches.value_of("ID").unwrap(), 16).unwrap();
 	let buf = controller.request(id).unwrap();
 
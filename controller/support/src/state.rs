@@ -25,7 +25,7 @@ fn main() {
 
 	loop {
 		match controller.state(Duration::from_secs(0)).unwrap() {
-			controller::State::Input { sequence, buttons, trigger, pad, orientation, acceleration, .. } => {
+			controller::State::Input { sequence, buttons, trigger, pad, acceleration, rotation, orientation, .. } => {
 				println!("{} {{", sequence);
 
 				if !buttons.is_empty() {
@@ -41,8 +41,9 @@ fn main() {
 				}
 
 				if matches.is_present("sensors") {
-					println!("\torientation: {:?}", orientation);
 					println!("\tacceleration: {:?}", acceleration);
+					println!("\trotation: {:?}", rotation);
+					println!("\torientation: {:?}", orientation.angles());
 				}
 
 				println!("}}");
@@ -1,52 +1,27 @@
 extern crate steamy_controller as controller;
 
-use controller::sound::Note;
-use std::thread;
+use controller::sound::{Note, Voice, Melody, Sequencer};
 use std::time::Duration;
 
-macro_rules! play {
-	($controller:expr, ?, $ms:expr) => {
-		thread::sleep(Duration::from_millis($ms));
-	};
-
-	($controller:expr, $note:ident, $ms:expr) => {
-		$controller.sound().left().note(Note::$note).duration(Duration::from_millis($ms)).play().unwrap();
-		$controller.sound().right().note(Note::$note).duration(Duration::from_millis($ms)).play().unwrap();
-		thread::sleep(Duration::from_millis($ms));
-	};
-
-	($controller:expr, $note:ident #, $ms:expr) => {
-		$controller.sound().left().note(Note::$note).sharp().duration(Duration::from_millis($ms)).play().unwrap();
-		$controller.sound().right().note(Note::$note).sharp().duration(Duration::from_millis($ms)).play().unwrap();
-		thread::sleep(Duration::from_millis($ms));
-	};
-
-	($controller:expr, $note:ident, $oct:expr, $ms:expr) => {
-		$controller.sound().left().note(Note::$note).octave($oct).duration(Duration::from_millis($ms)).play().unwrap();
-		$controller.sound().right().note(Note::$note).octave($oct).duration(Duration::from_millis($ms)).play().unwrap();
-		thread::sleep(Duration::from_millis($ms));
-	};
-
-	($controller:expr, $note:ident #, $oct:expr, $ms:expr) => {
-		$controller.sound().left().note(Note::$note).sharp().octave($oct).duration(Duration::from_millis($ms)).play().unwrap();
-		$controller.sound().right().note(Note::$note).sharp().octave($oct).duration(Duration::from_millis($ms)).play().unwrap();
-		thread::sleep(Duration::from_millis($ms));
-	};
-}
-
 fn main() {
 	let mut manager    = controller::Manager::new().unwrap();
 	let mut controller = manager.open().unwrap();
 
-	play!(controller, C, 7, 200);
-	play!(controller, C, 7, 200);
-	play!(controller, C, 7, 200);
-	play!(controller, C, 7, 500);
+	let ms = Duration::from_millis;
+	let melody = Melody::new().both(Voice::new()
+		.note(Note::C, false, 7, ms(200))
+		.note(Note::C, false, 7, ms(200))
+		.note(Note::C, false, 7, ms(200))
+		.note(Note::C, false, 7, ms(500))
+
+		.note(Note::G, false, 6, ms(500))
+		.note(Note::A, true, 6, ms(500))
 
-	play!(controller, G, 6, 500);
-	play!(controller, A#, 6, 500);
+		.note(Note::C, false, 7, ms(300))
+		.note(Note::A, true, 6, ms(200))
+		.note(Note::C, false, 7, ms(500)));
 
-	play!(controller, C, 7, 300);
-	play!(controller, A#, 6, 200);
-	play!(controller, C, 7, 500);
+	let mut sequencer = Sequencer::new();
+	sequencer.play(melody);
+	sequencer.wait(&mut controller).unwrap();
 }
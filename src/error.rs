@@ -1,5 +1,6 @@
 use std::fmt;
 use std::error;
+use std::io;
 use controller;
 use vdf;
 
@@ -8,6 +9,7 @@ use uinput;
 
 #[derive(Debug)]
 pub enum Error {
+	Io(io::Error),
 	Controller(controller::Error),
 	Vdf(vdf::Error),
 
@@ -18,6 +20,12 @@ pub enum Error {
 	InvalidParameter,
 }
 
+impl From<io::Error> for Error {
+	fn from(value: io::Error) -> Self {
+		Error::Io(value)
+	}
+}
+
 impl From<controller::Error> for Error {
 	fn from(value: controller::Error) -> Self {
 		Error::Controller(value)
@@ -39,16 +47,25 @@ impl From<uinput::Error> for Error {
 
 impl fmt::Display for Error {
 	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-		f.write_str(error::Error::description(self))
+		match self {
+			&Error::Controller(ref err) =>
+				fmt::Display::fmt(err, f),
+
+			_ =>
+				f.write_str(error::Error::description(self))
+		}
 	}
 }
 
 impl error::Error for Error {
 	fn description(&self) -> &str {
 		match self {
-			&Error::Controller(ref err) =>
+			&Error::Io(ref err) =>
 				err.description(),
 
+			&Error::Controller(..) =>
+				"Controller error.",
+
 			&Error::Vdf(ref err) =>
 				err.description(),
 
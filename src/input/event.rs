@@ -8,8 +8,10 @@ pub enum Event {
 	Button(Button, bool),
 	Trigger(Trigger),
 	Pad(Pad),
-	Orientation(controller::Angles),
-	Acceleration(controller::Angles),
+	Acceleration(controller::Vector),
+	Rotation(controller::Vector),
+	Orientation(controller::Quaternion),
+	LowBattery(controller::Battery),
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -1,50 +1,435 @@
 use std::ops::Deref;
+use std::cmp;
 use std::thread;
-use std::sync::mpsc::{channel, Receiver};
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Sender, Receiver, TryRecvError, RecvTimeoutError};
 use std::time::{Instant, Duration};
-use controller;
+use controller::{self, Controller, hotplug, haptic};
+use controller::transport::Transport;
 use super::{Event, State};
 
+/// Identifier of the controller an event comes from, the lowest free one is
+/// given to each newly attached controller.
+pub type Id = usize;
+
+/// Haptic feedback to play on the controllers.
+#[derive(Clone, Debug)]
+pub enum Haptic {
+	/// Play the pattern, replacing the ones playing on the same pads; the key
+	/// is chosen by the sender to stop it later.
+	Play(u32, haptic::Pattern),
+
+	/// Stop the pattern played with the key.
+	Stop(u32),
+}
+
 pub struct Input {
-	rx: Receiver<(Instant, Event)>,
+	rx:      Receiver<(Instant, Id, Event)>,
+	haptic:  Arc<Mutex<HashMap<Id, Sender<Haptic>>>>,
+	readers: Arc<Mutex<HashMap<Id, JoinHandle<()>>>>,
+	running: Arc<AtomicBool>,
+}
+
+/// How long to wait before opening a controller again after its reader
+/// stopped on its own, doubled on every failure.
+const BACKOFF: u64 = 500;
+
+/// The longest wait between attempts.
+const MAX_BACKOFF: u64 = 10_000;
+
+struct Slot {
+	device:  controller::Device,
+	alive:   Arc<AtomicBool>,
+	backoff: Duration,
 }
 
 impl Input {
-	pub fn spawn() -> Input {
+	/// Spawn the input threads, when `restore` is set the controllers are put
+	/// back in lizard mode when they're let go.
+	pub fn spawn(restore: bool) -> Input {
 		let (tx, rx) = channel();
+		let haptic   = Arc::new(Mutex::new(HashMap::new()));
+		let senders  = haptic.clone();
+		let readers  = Arc::new(Mutex::new(HashMap::new()));
+		let handles  = readers.clone();
+		let running  = Arc::new(AtomicBool::new(true));
+		let active   = running.clone();
 
 		thread::spawn(move || {
-			let mut manager = controller::Manager::new().unwrap();
-			let mut state   = State::default();
+			let     manager  = controller::Manager::new().unwrap();
+			let     hotplug  = manager.hotplug();
+			let mut attached = Vec::<Option<Slot>>::new();
+			let mut retries  = Vec::<(controller::Device, Instant, Duration)>::new();
+
+			// Readers report here when they stop.
+			let (etx, exits) = channel::<(Id, Arc<AtomicBool>)>();
+
+			let attach = |attached: &mut Vec<Option<Slot>>, device: controller::Device, backoff: Duration| {
+				let mut handles = handles.lock().unwrap();
+
+				// Don't start readers that wouldn't be waited for.
+				if !active.load(Ordering::SeqCst) {
+					return;
+				}
+
+				let alive = Arc::new(AtomicBool::new(true));
+				let entry = Some(Slot { device: device.clone(), alive: alive.clone(), backoff: backoff });
+
+				let id = if let Some(id) = attached.iter().position(|d| d.is_none()) {
+					attached[id] = entry;
+					id
+				}
+				else {
+					attached.push(entry);
+					attached.len() - 1
+				};
+
+				let tx            = tx.clone();
+				let etx           = etx.clone();
+				let (htx, haptic) = channel();
+				senders.lock().unwrap().insert(id, htx);
+
+				handles.insert(id, thread::spawn(move || {
+					run(id, &device, &alive, &tx, &haptic, restore);
+					let _ = etx.send((id, alive));
+				}));
+			};
+
+			let detach = |attached: &mut Vec<Option<Slot>>, id: Id| -> Option<Slot> {
+				let slot = attached[id].take();
+
+				if let Some(ref slot) = slot {
+					senders.lock().unwrap().remove(&id);
+					slot.alive.store(false, Ordering::Relaxed);
+
+					// The reader stops soon, wait for it so the slot can be reused.
+					let reader = handles.lock().unwrap().remove(&id);
+
+					if let Some(reader) = reader {
+						let _ = reader.join();
+					}
+				}
+
+				slot
+			};
+
+			while active.load(Ordering::SeqCst) {
+				match hotplug.recv_timeout(Duration::from_millis(100)) {
+					Ok(hotplug::Event::Arrived(ref device)) if device.is_bootloader() => {
+						let _ = writeln!(io::stderr(), "steamy: a controller is in bootloader mode, finish the firmware update through Steam");
+					}
+
+					Ok(hotplug::Event::Arrived(device)) =>
+						attach(&mut attached, device, Duration::from_millis(BACKOFF)),
+
+					Ok(hotplug::Event::Left(device)) => {
+						retries.retain(|&(ref d, _, _)| *d != device);
+
+						for id in 0 .. attached.len() {
+							if attached[id].as_ref().map_or(false, |s| s.device == device) {
+								detach(&mut attached, id);
+							}
+						}
+					}
 
-			loop {
-				let mut controller = wait!(manager.open(), 500);
+					Err(RecvTimeoutError::Timeout) =>
+						(),
 
-				end!(tx.send((Instant::now(), Event::Connected)));
+					Err(RecvTimeoutError::Disconnected) =>
+						break,
+				}
 
-				loop {
-					let new = end!(controller.state(Duration::from_secs(0)));
-					let at  = Instant::now();
+				// A reader stopping while its controller is still attached
+				// couldn't open it or hit an error, try again later.
+				while let Ok((id, alive)) = exits.try_recv() {
+					let current = attached.get(id).and_then(|s| s.as_ref())
+						.map_or(false, |s| Arc::ptr_eq(&s.alive, &alive));
 
-					for event in state.update(new) {
-						end!(tx.send((at, event)))
+					if current {
+						if let Some(slot) = detach(&mut attached, id) {
+							retries.push((slot.device, Instant::now() + slot.backoff, slot.backoff));
+						}
 					}
 				}
 
-				end!(tx.send((Instant::now(), Event::Disconnected)));
+				let now = Instant::now();
+				let (due, later) = retries.drain(..).partition::<Vec<_>, _>(|&(_, at, _)| at <= now);
+				retries = later;
+
+				for (device, _, backoff) in due {
+					attach(&mut attached, device, cmp::min(backoff * 2, Duration::from_millis(MAX_BACKOFF)));
+				}
 			}
 		});
 
 		Input {
-			rx: rx
+			rx:      rx,
+			haptic:  haptic,
+			readers: readers,
+			running: running,
+		}
+	}
+
+	/// Stop reading from the controllers and wait for them to be let go.
+	pub fn shutdown(self) {
+		self.running.store(false, Ordering::SeqCst);
+
+		// Hold the readers so none is started in the meantime, they stop as
+		// soon as their haptic channel is closed.
+		let mut readers = self.readers.lock().unwrap();
+		self.haptic.lock().unwrap().clear();
+
+		for (_, reader) in readers.drain() {
+			let _ = reader.join();
+		}
+	}
+
+	/// Play haptic feedback on the given controller.
+	pub fn haptic(&self, id: Id, value: Haptic) {
+		let mut senders = self.haptic.lock().unwrap();
+		let     gone    = senders.get(&id).map_or(false, |h| h.send(value).is_err());
+
+		if gone {
+			senders.remove(&id);
+		}
+	}
+}
+
+fn run(id: Id, device: &controller::Device, alive: &AtomicBool, tx: &Sender<(Instant, Id, Event)>, haptic: &Receiver<Haptic>, restore: bool) {
+	let manager = if let Ok(manager) = controller::Manager::new() {
+		manager
+	}
+	else {
+		return;
+	};
+
+	let mut controller = if let Ok(controller) = manager.open_device(device) {
+		controller
+	}
+	else {
+		return;
+	};
+
+	controller.restore(restore);
+	read(id, &mut controller, alive, tx, haptic);
+}
+
+/// Send the events of the controller until it goes away or the input is shut
+/// down, playing the haptic feedback meanwhile.
+fn read<T: Transport>(id: Id, controller: &mut Controller<T>, alive: &AtomicBool, tx: &Sender<(Instant, Id, Event)>, haptic: &Receiver<Haptic>) {
+	let mut state     = State::default();
+	let mut scheduler = haptic::Scheduler::new();
+	let mut playing   = HashMap::new();
+	let mut checked   = Instant::now();
+
+	// The wireless receiver stays attached while controllers come and go.
+	let mut connected = controller.is_connected();
+
+	if connected && tx.send((Instant::now(), id, Event::Connected)).is_err() {
+		return;
+	}
+
+	while alive.load(Ordering::Relaxed) {
+		loop {
+			match haptic.try_recv() {
+				Ok(Haptic::Play(key, pattern)) => {
+					playing.insert(key, scheduler.play(pattern, haptic::Mode::Replace));
+				}
+
+				Ok(Haptic::Stop(key)) =>
+					if let Some(id) = playing.remove(&key) {
+						scheduler.cancel(id);
+					},
+
+				Err(TryRecvError::Empty) =>
+					break,
+
+				Err(TryRecvError::Disconnected) =>
+					return,
+			}
+		}
+
+		// Keep the timeout short so haptic feedback is played in time.
+		let timeout = match scheduler.tick(controller, Instant::now()) {
+			Ok(Some(next)) if next < Duration::from_millis(20) =>
+				cmp::max(next, Duration::from_millis(1)),
+
+			Ok(_) =>
+				Duration::from_millis(20),
+
+			Err(_) => {
+				scheduler.stop();
+				Duration::from_millis(20)
+			}
+		};
+
+		let events = match controller.state(timeout) {
+			Ok(controller::State::Idle { .. }) if !connected =>
+				vec![Event::Connected],
+
+			Ok(new) =>
+				state.update(new),
+
+			Err(ref err) if err.is_timeout() => {
+				if checked.elapsed() < Duration::from_millis(500) {
+					continue;
+				}
+
+				checked = Instant::now();
+
+				if controller.is_connected() != connected {
+					vec![if connected { Event::Disconnected } else { Event::Connected }]
+				}
+				else {
+					continue;
+				}
+			}
+
+			// A malformed report doesn't mean the controller is gone.
+			Err(controller::Error::Parse { .. }) =>
+				continue,
+
+			Err(_) =>
+				break,
+		};
+
+		let at = Instant::now();
+
+		for event in events {
+			match event {
+				Event::Connected if connected =>
+					continue,
+
+				Event::Disconnected if !connected =>
+					continue,
+
+				Event::Connected =>
+					connected = true,
+
+				Event::Disconnected =>
+					connected = false,
+
+				_ if !connected => {
+					connected = true;
+
+					if tx.send((at, id, Event::Connected)).is_err() {
+						return;
+					}
+				}
+
+				_ =>
+					()
+			}
+
+			if tx.send((at, id, event)).is_err() {
+				return;
+			}
 		}
 	}
+
+	if connected {
+		let _ = tx.send((Instant::now(), id, Event::Disconnected));
+	}
 }
 
 impl Deref for Input {
-	type Target = Receiver<(Instant, Event)>;
+	type Target = Receiver<(Instant, Id, Event)>;
 
 	fn deref(&self) -> &Self::Target {
 		&self.rx
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use std::thread;
+	use std::sync::Arc;
+	use std::sync::atomic::{AtomicBool, Ordering};
+	use std::sync::mpsc::channel;
+	use std::time::Duration;
+	use controller::{Controller, button};
+	use controller::haptic::{Pattern, Side};
+	use controller::transport::Emulator;
+	use input::{Event, Button};
+	use super::{Haptic, read};
+
+	/// Read the emulated controller until it's turned off, returning the
+	/// events and the feature reports sent to it.
+	fn play(emulator: Emulator, haptic: Vec<Haptic>) -> (Vec<Event>, Vec<Vec<u8>>) {
+		let (tx, rx)        = channel();
+		let (sender, queue) = channel();
+		let alive           = Arc::new(AtomicBool::new(true));
+
+		for value in haptic {
+			sender.send(value).unwrap();
+		}
+
+		let reader = {
+			let alive = alive.clone();
+
+			thread::spawn(move || {
+				let _sender        = sender;
+				let mut controller = Controller::new(emulator, 0x1102).unwrap();
+				controller.transport_mut().clear();
+
+				read(0, &mut controller, &alive, &tx, &queue);
+				controller.transport().sent().to_vec()
+			})
+		};
+
+		let mut events = Vec::new();
+
+		while let Ok((_, id, event)) = rx.recv_timeout(Duration::from_secs(1)) {
+			assert_eq!(0, id);
+			events.push(event);
+
+			if event == Event::Disconnected {
+				break;
+			}
+		}
+
+		alive.store(false, Ordering::Relaxed);
+		(events, reader.join().unwrap())
+	}
+
+	#[test]
+	fn events() {
+		let (events, _) = play(Emulator::new()
+			.at(Duration::from_millis(10)).press(button::A)
+			.at(Duration::from_millis(20)).release(button::A)
+			.at(Duration::from_millis(30)).power(false), vec![]);
+
+		assert_eq!(vec![
+			Event::Connected,
+			Event::Button(Button::A, true),
+			Event::Button(Button::A, false),
+			Event::Disconnected,
+		], events);
+	}
+
+	#[test]
+	fn haptic() {
+		let emulator = Emulator::new()
+			.at(Duration::from_millis(10)).power(false);
+
+		let (_, sent) = play(emulator.clone(), vec![
+			Haptic::Play(1, Pattern::tick(Side::Left)),
+		]);
+
+		assert!(sent.iter().any(|packet| packet[0] == 0x8f));
+
+		// Only the stopped pattern is cancelled.
+		let (_, sent) = play(emulator.clone(), vec![
+			Haptic::Play(1, Pattern::tick(Side::Left)),
+			Haptic::Play(2, Pattern::tick(Side::Right)),
+			Haptic::Stop(1),
+		]);
+
+		assert!(sent.iter().any(|packet| packet[0] == 0x8f && packet[2] == 0x00));
+		assert!(!sent.iter().any(|packet| packet[0] == 0x8f && packet[2] == 0x01));
+	}
+}
@@ -2,7 +2,7 @@
 mod util;
 
 mod input;
-pub use self::input::Input;
+pub use self::input::{Input, Id, Haptic};
 
 mod event;
 pub use self::event::{Event, Button, Trigger, Pad};
@@ -10,6 +10,6 @@ pub use self::event::{Event, Button, Trigger, Pad};
 mod state;
 pub use self::state::State;
 
-pub fn spawn() -> Input {
-	Input::spawn()
+pub fn spawn(restore: bool) -> Input {
+	Input::spawn(restore)
 }
@@ -1,13 +1,18 @@
 use controller::{self, button};
 use super::{Event, Button, Trigger, Pad};
 
+/// Battery percentage considered low.
+const LOW: u8 = 15;
+
 #[derive(Debug)]
 pub struct State {
 	buttons:      controller::Button,
 	trigger:      controller::Trigger,
 	pad:          controller::Pad,
-	orientation:  controller::Angles,
-	acceleration: controller::Angles,
+	acceleration: controller::Vector,
+	rotation:     controller::Vector,
+	orientation:  controller::Quaternion,
+	low:          bool,
 }
 
 impl Default for State {
@@ -16,8 +21,10 @@ impl Default for State {
 			buttons:      controller::Button::empty(),
 			trigger:      Default::default(),
 			pad:          Default::default(),
-			orientation:  Default::default(),
 			acceleration: Default::default(),
+			rotation:     Default::default(),
+			orientation:  Default::default(),
+			low:          false,
 		}
 	}
 }
@@ -35,7 +42,7 @@ impl State {
 				events.push(Event::Disconnected);
 			}
 
-			controller::State::Input { buttons, trigger, pad, orientation, acceleration, .. } => {
+			controller::State::Input { buttons, trigger, pad, acceleration, rotation, orientation, .. } => {
 				button!(events, self.buttons, buttons, {
 					button::A => Button::A,
 					button::B => Button::B,
@@ -86,22 +93,35 @@ impl State {
 					events.push(Event::Pad(Pad::Right(pad.right)));
 				}
 
-				if self.orientation != orientation {
-					events.push(Event::Orientation(orientation));
-				}
-
 				if self.acceleration != acceleration {
 					events.push(Event::Acceleration(acceleration));
 				}
 
+				if self.rotation != rotation {
+					events.push(Event::Rotation(rotation));
+				}
+
+				if self.orientation != orientation {
+					events.push(Event::Orientation(orientation));
+				}
+
 				self.buttons      = buttons;
 				self.trigger      = trigger;
 				self.pad          = pad;
-				self.orientation  = orientation;
 				self.acceleration = acceleration;
+				self.rotation     = rotation;
+				self.orientation  = orientation;
+			}
+
+			controller::State::Idle { battery, .. } => {
+				if battery.percent <= LOW && !self.low {
+					events.push(Event::LowBattery(battery));
+				}
+
+				self.low = battery.percent <= LOW;
 			}
 
-			controller::State::Idle { .. } =>
+			controller::State::Unknown { .. } =>
 				(),
 		}
 
@@ -9,6 +9,12 @@ use clap::{Arg, App};
 #[cfg(target_os = "linux")]
 extern crate uinput;
 
+#[cfg(target_os = "linux")]
+extern crate libc;
+
+use std::time::Duration;
+use std::sync::mpsc::RecvTimeoutError;
+
 #[macro_use]
 mod util;
 
@@ -26,6 +32,9 @@ pub use input::Input;
 mod mapper;
 pub use mapper::Mapper;
 
+#[cfg(target_os = "linux")]
+mod signal;
+
 fn main() {
 	let matches = App::new("steamy")
 		.version("1.0")
@@ -33,15 +42,45 @@ fn main() {
 			.required(true)
 			.index(1)
 			.help("Path to the config file."))
+		.arg(Arg::with_name("no-restore")
+			.long("no-restore")
+			.help("Leave the controllers out of lizard mode on exit."))
 		.get_matches();
 
+	// Catch the signals before opening anything, so nothing is left out of
+	// lizard mode.
+	#[cfg(target_os = "linux")]
+	signal::install();
+
 	let     config = config::load(matches.value_of("CONFIG").unwrap()).expect("config: failed to load");
-	let     input  = input::spawn();
+	let     input  = input::spawn(!matches.is_present("no-restore"));
 	let mut mapper = mapper::new(&config).expect("mapper: failed to create");
 
 	println!("{:#?}", config);
 
-	for (at, event) in input.iter() {
-		end!(mapper.event(at, event));
+	loop {
+		#[cfg(target_os = "linux")]
+		{
+			if signal::is_interrupted() {
+				break;
+			}
+		}
+
+		match input.recv_timeout(Duration::from_millis(10)) {
+			Ok((at, id, event)) =>
+				end!(mapper.event(at, id, event)),
+
+			Err(RecvTimeoutError::Timeout) =>
+				(),
+
+			Err(RecvTimeoutError::Disconnected) =>
+				break,
+		}
+
+		for (id, haptic) in end!(mapper.feedback()) {
+			input.haptic(id, haptic);
+		}
 	}
+
+	input.shutdown();
 }
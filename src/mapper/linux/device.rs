@@ -0,0 +1,214 @@
+//! The virtual device the events are sent to.
+//!
+//! The `uinput` crate can't create devices supporting force feedback, so the
+//! device is created here with the raw ioctls; games send rumble to the same
+//! device they read the input from.
+
+use std::io;
+use std::mem;
+use std::ptr;
+use std::ffi::CString;
+use std::os::unix::io::RawFd;
+use libc::{self, c_int, c_ulong, c_void};
+use uinput;
+use uinput::event::{Kind, Code};
+use {Result as Res};
+use input::Haptic;
+use super::{Device, Rumble, ffi};
+
+/// Virtual device builder.
+#[derive(Clone, Debug)]
+pub struct Builder {
+	name:     String,
+	events:   Vec<uinput::Event>,
+	absolute: Vec<(uinput::Event, i32, i32, i32, i32)>,
+	effects:  u32,
+}
+
+impl Builder {
+	/// Create a builder for a device with the given name.
+	pub fn new<S: Into<String>>(name: S) -> Builder {
+		Builder {
+			name:     name.into(),
+			events:   Vec::new(),
+			absolute: Vec::new(),
+			effects:  0,
+		}
+	}
+
+	/// Enable the event.
+	pub fn event<E: Into<uinput::Event>>(mut self, event: E) -> Self {
+		self.events.push(event.into());
+		self
+	}
+
+	/// Enable the absolute axis with the given range, fuzz and flat.
+	pub fn absolute<E: Into<uinput::Event>>(mut self, event: E, min: i32, max: i32, fuzz: i32, flat: i32) -> Self {
+		let event = event.into();
+
+		self.events.push(event);
+		self.absolute.push((event, min, max, fuzz, flat));
+		self
+	}
+
+	/// Enable rumble with the given number of effects.
+	pub fn effects(mut self, value: u32) -> Self {
+		self.effects = value;
+		self
+	}
+
+	/// Create the device.
+	pub fn create(&self) -> Res<Virtual> {
+		let path = CString::new("/dev/uinput").unwrap();
+		let fd   = unsafe { libc::open(path.as_ptr(), libc::O_RDWR | libc::O_NONBLOCK) };
+
+		if fd < 0 {
+			return Err(io::Error::last_os_error().into());
+		}
+
+		// The descriptor is closed on drop from here on.
+		let mut device = Virtual {
+			fd:      fd,
+			created: false,
+			rumble:  Rumble::new(),
+		};
+
+		let mut setup: ffi::uinput_user_dev = unsafe { mem::zeroed() };
+		let     name                         = self.name.as_bytes();
+		let     length                       = if name.len() < ffi::NAME_SIZE { name.len() } else { ffi::NAME_SIZE - 1 };
+
+		setup.name[.. length].copy_from_slice(&name[.. length]);
+		setup.id.bustype     = ffi::BUS_VIRTUAL;
+		setup.ff_effects_max = self.effects;
+
+		for &event in &self.events {
+			let request = match event.kind() as u16 {
+				ffi::EV_KEY => ffi::set_keybit(),
+				ffi::EV_REL => ffi::set_relbit(),
+				ffi::EV_ABS => ffi::set_absbit(),
+				_           => continue,
+			};
+
+			device.set(ffi::set_evbit(), event.kind())?;
+			device.set(request, event.code())?;
+		}
+
+		for &(event, min, max, fuzz, flat) in &self.absolute {
+			let code = event.code() as usize;
+
+			if code < ffi::ABS_CNT {
+				setup.absmin[code]  = min;
+				setup.absmax[code]  = max;
+				setup.absfuzz[code] = fuzz;
+				setup.absflat[code] = flat;
+			}
+		}
+
+		if self.effects > 0 {
+			device.set(ffi::set_evbit(), ffi::EV_FF as c_int)?;
+			device.set(ffi::set_ffbit(), ffi::FF_RUMBLE as c_int)?;
+		}
+
+		let size = unsafe {
+			libc::write(fd, &setup as *const _ as *const c_void, mem::size_of::<ffi::uinput_user_dev>())
+		};
+
+		if size < 0 {
+			return Err(io::Error::last_os_error().into());
+		}
+
+		device.set(ffi::dev_create(), 0)?;
+
+		device.created = true;
+		Ok(device)
+	}
+}
+
+/// A virtual device created through uinput.
+pub struct Virtual {
+	fd:      RawFd,
+	created: bool,
+	rumble:  Rumble,
+}
+
+impl Virtual {
+	fn set(&self, request: c_ulong, value: c_int) -> Res<()> {
+		if unsafe { libc::ioctl(self.fd, request as _, value) } < 0 {
+			Err(io::Error::last_os_error().into())
+		}
+		else {
+			Ok(())
+		}
+	}
+
+	fn write(&mut self, kind: u16, code: u16, value: i32) -> Res<()> {
+		let mut event: ffi::input_event = unsafe { mem::zeroed() };
+		event.kind  = kind;
+		event.code  = code;
+		event.value = value;
+
+		let size = unsafe {
+			libc::write(self.fd, &event as *const _ as *const c_void, mem::size_of::<ffi::input_event>())
+		};
+
+		if size < 0 {
+			Err(io::Error::last_os_error().into())
+		}
+		else {
+			Ok(())
+		}
+	}
+}
+
+impl Device for Virtual {
+	fn send(&mut self, event: uinput::Event, value: i32) -> Res<()> {
+		self.write(event.kind() as u16, event.code() as u16, value)
+	}
+
+	fn synchronize(&mut self) -> Res<()> {
+		self.write(ffi::EV_SYN, ffi::SYN_REPORT, 0)
+	}
+
+	fn feedback(&mut self) -> Res<Vec<Haptic>> {
+		let mut result = Vec::new();
+
+		loop {
+			let mut event: ffi::input_event = unsafe { mem::zeroed() };
+			let     size                    = unsafe {
+				libc::read(self.fd, &mut event as *mut _ as *mut c_void, mem::size_of::<ffi::input_event>())
+			};
+
+			if size < 0 {
+				let err = io::Error::last_os_error();
+
+				if err.kind() == io::ErrorKind::WouldBlock {
+					break;
+				}
+
+				return Err(err.into());
+			}
+
+			if size as usize != mem::size_of::<ffi::input_event>() {
+				break;
+			}
+
+			if let Some(haptic) = self.rumble.handle(self.fd, event.kind, event.code, event.value)? {
+				result.push(haptic);
+			}
+		}
+
+		Ok(result)
+	}
+}
+
+impl Drop for Virtual {
+	fn drop(&mut self) {
+		unsafe {
+			if self.created {
+				libc::ioctl(self.fd, ffi::dev_destroy() as _, ptr::null_mut::<c_void>());
+			}
+
+			libc::close(self.fd);
+		}
+	}
+}
@@ -0,0 +1,149 @@
+//! The uinput ABI, see `linux/uinput.h` and `linux/input.h`.
+
+#![allow(non_camel_case_types)]
+
+use libc::{c_int, c_ulong, timeval};
+
+pub const EV_SYN:      u16 = 0x00;
+pub const EV_KEY:      u16 = 0x01;
+pub const EV_REL:      u16 = 0x02;
+pub const EV_ABS:      u16 = 0x03;
+pub const EV_FF:       u16 = 0x15;
+pub const EV_UINPUT:   u16 = 0x0101;
+pub const SYN_REPORT:  u16 = 0x00;
+pub const FF_RUMBLE:   u16 = 0x50;
+pub const FF_UPLOAD:   u16 = 1;
+pub const FF_ERASE:    u16 = 2;
+pub const BUS_VIRTUAL: u16 = 0x06;
+pub const ABS_CNT:     usize = 0x40;
+pub const NAME_SIZE:   usize = 80;
+
+#[repr(C)]
+pub struct input_id {
+	pub bustype: u16,
+	pub vendor:  u16,
+	pub product: u16,
+	pub version: u16,
+}
+
+#[repr(C)]
+pub struct uinput_user_dev {
+	pub name:           [u8; NAME_SIZE],
+	pub id:             input_id,
+	pub ff_effects_max: u32,
+	pub absmax:         [i32; ABS_CNT],
+	pub absmin:         [i32; ABS_CNT],
+	pub absfuzz:        [i32; ABS_CNT],
+	pub absflat:        [i32; ABS_CNT],
+}
+
+#[repr(C)]
+pub struct input_event {
+	pub time:  timeval,
+	pub kind:  u16,
+	pub code:  u16,
+	pub value: i32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct ff_replay {
+	pub length: u16,
+	pub delay:  u16,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct ff_trigger {
+	pub button:   u16,
+	pub interval: u16,
+}
+
+/// Only the rumble member of the union is used, the padding brings it to
+/// the size of the periodic one, which is the largest.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct ff_rumble_effect {
+	pub strong_magnitude: u16,
+	pub weak_magnitude:   u16,
+
+	_padding:     [u16; 7],
+	_custom_len:  u32,
+	_custom_data: *mut i16,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct ff_effect {
+	pub kind:      u16,
+	pub id:        i16,
+	pub direction: u16,
+	pub trigger:   ff_trigger,
+	pub replay:    ff_replay,
+	pub u:         ff_rumble_effect,
+}
+
+#[repr(C)]
+pub struct uinput_ff_upload {
+	pub request_id: u32,
+	pub retval:     i32,
+	pub effect:     ff_effect,
+	pub old:        ff_effect,
+}
+
+#[repr(C)]
+pub struct uinput_ff_erase {
+	pub request_id: u32,
+	pub retval:     i32,
+	pub effect_id:  u32,
+}
+
+fn ioc(dir: c_ulong, nr: c_ulong, size: usize) -> c_ulong {
+	(dir << 30) | ((size as c_ulong) << 16) | ((b'U' as c_ulong) << 8) | nr
+}
+
+pub fn dev_create() -> c_ulong {
+	ioc(0, 1, 0)
+}
+
+pub fn dev_destroy() -> c_ulong {
+	ioc(0, 2, 0)
+}
+
+pub fn set_evbit() -> c_ulong {
+	ioc(1, 100, ::std::mem::size_of::<c_int>())
+}
+
+pub fn set_keybit() -> c_ulong {
+	ioc(1, 101, ::std::mem::size_of::<c_int>())
+}
+
+pub fn set_relbit() -> c_ulong {
+	ioc(1, 102, ::std::mem::size_of::<c_int>())
+}
+
+pub fn set_absbit() -> c_ulong {
+	ioc(1, 103, ::std::mem::size_of::<c_int>())
+}
+
+pub fn set_ffbit() -> c_ulong {
+	ioc(1, 107, ::std::mem::size_of::<c_int>())
+}
+
+pub fn begin_upload() -> c_ulong {
+	ioc(3, 200, ::std::mem::size_of::<uinput_ff_upload>())
+}
+
+pub fn end_upload() -> c_ulong {
+	ioc(1, 201, ::std::mem::size_of::<uinput_ff_upload>())
+}
+
+pub fn begin_erase() -> c_ulong {
+	ioc(3, 202, ::std::mem::size_of::<uinput_ff_erase>())
+}
+
+pub fn end_erase() -> c_ulong {
+	ioc(1, 203, ::std::mem::size_of::<uinput_ff_erase>())
+}
+
+pub const EINVAL: c_int = 22;
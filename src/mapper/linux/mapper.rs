@@ -4,20 +4,41 @@ use uinput;
 use {Result as Res};
 use util::iter;
 use config::{self, Config, group, Binding};
-use input::{self, Event};
-use super::{Preset, Button};
+use input::{self, Event, Haptic, Id};
+use super::{Preset, Button, Device, Builder, Virtual, rumble};
 
-pub struct Mapper<'a> {
-	config:  &'a Config,
-	device:  uinput::Device,
+pub struct Mapper<'a, D: Device = Virtual> {
+	config:      &'a Config,
+	create:      Box<FnMut() -> Res<D> + 'a>,
+	controllers: HashMap<Id, State<'a, D>>,
+}
+
+/// The mapping state of a controller, with the device its events go to.
+struct State<'a, D: Device> {
+	device:  D,
 	preset:  u32,
 	presets: HashMap<u32, Preset<'a>>,
 	pressed: HashSet<uinput::Event>,
 }
 
+impl<'a, D: Device> State<'a, D> {
+	fn load(config: &'a Config, device: D) -> Res<State<'a, D>> {
+		let presets = config.presets.keys().map(|&id|
+			Ok((id, Preset::load(id, config)?)));
+
+		Ok(State {
+			device:  device,
+			preset:  0,
+			presets: presets.collect::<Res<HashMap<u32, Preset>>>()?,
+			pressed: HashSet::new(),
+		})
+	}
+}
+
 impl<'a> Mapper<'a> {
 	pub fn new(config: &Config) -> Res<Mapper> {
-		let builder = uinput::default()?.name("steamy")?;
+		// Enable rumble, it's played on the pads.
+		let builder = Builder::new("steamy").effects(rumble::EFFECTS);
 
 		// Enable events from modes.
 		let builder = config.groups.iter()
@@ -26,12 +47,12 @@ impl<'a> Mapper<'a> {
 			.fold(builder, |builder, mode|
 				match mode {
 					group::Mode::JoystickMove | group::Mode::MouseJoystick =>
-						builder.event(uinput::event::absolute::Position::X).unwrap().min(-32768).max(32767).fuzz(16).flat(128)
-						       .event(uinput::event::absolute::Position::Y).unwrap().min(-32768).max(32767).fuzz(16).flat(128),
+						builder.absolute(uinput::event::absolute::Position::X, -32768, 32767, 16, 128)
+						       .absolute(uinput::event::absolute::Position::Y, -32768, 32767, 16, 128),
 
 					group::Mode::AbsoluteMouse =>
-						builder.event(uinput::event::relative::Position::X).unwrap()
-						       .event(uinput::event::relative::Position::Y).unwrap(),
+						builder.event(uinput::event::relative::Position::X)
+						       .event(uinput::event::relative::Position::Y),
 
 					_ =>
 						builder
@@ -70,38 +91,57 @@ impl<'a> Mapper<'a> {
 			.filter(|&binding|
 				if let &Binding::Action(..) = binding { false } else { true })
 			.fold(builder, |builder, binding|
-				builder.event(binding).unwrap());
+				builder.event(binding));
 
-		let presets = config.presets.keys().map(|&id|
-			Ok((id, Preset::load(id, config)?)));
+		// Every controller gets its own device, so rumble is played on the
+		// controller the game reads from.
+		Mapper::with_devices(config, move || builder.create())
+	}
+}
 
+impl<'a, D: Device> Mapper<'a, D> {
+	/// Create a mapper sending the events of every controller to a device
+	/// created with the given function.
+	pub fn with_devices<F: FnMut() -> Res<D> + 'a>(config: &'a Config, create: F) -> Res<Mapper<'a, D>> {
 		Ok(Mapper {
-			config:  config,
-			device:  builder.create()?,
-			preset:  0,
-			presets: presets.collect::<Res<HashMap<u32, Preset>>>()?,
-			pressed: HashSet::new(),
+			config:      config,
+			create:      Box::new(create),
+			controllers: HashMap::new(),
 		})
 	}
 
-	pub fn event(&mut self, at: Instant, event: Event) -> Res<()> {
-		match event {
-			Event::Connected => (),
-			Event::Disconnected => {
-				for event in self.pressed.drain() {
-					self.device.send(event, 0)?;
+	pub fn event(&mut self, at: Instant, id: Id, event: Event) -> Res<()> {
+		// Release what the controller was holding, its device goes away with
+		// it.
+		if let Event::Disconnected = event {
+			if let Some(mut state) = self.controllers.remove(&id) {
+				for event in state.pressed.drain() {
+					state.device.send(event, 0)?;
 				}
+
+				state.device.synchronize()?;
 			}
 
-			Event::Button(btn, press) if switch!(self, btn) => {
-				button!(self, switch, at, btn, press);
+			return Ok(());
+		}
+
+		if !self.controllers.contains_key(&id) {
+			let state = State::load(self.config, (self.create)()?)?;
+			self.controllers.insert(id, state);
+		}
+
+		let state = self.controllers.get_mut(&id).unwrap();
+
+		match event {
+			Event::Button(btn, press) if switch!(state, btn) => {
+				button!(state, switch, at, btn, press);
 			}
 
 			Event::Button(btn@input::Button::A, press) |
 			Event::Button(btn@input::Button::B, press) |
 			Event::Button(btn@input::Button::X, press) |
 			Event::Button(btn@input::Button::Y, press) => {
-				button!(self, button_diamond, at, btn, press)?;
+				button!(state, button_diamond, at, btn, press)?;
 			}
 
 			Event::Button(btn@input::Button::Up, press) |
@@ -109,26 +149,140 @@ impl<'a> Mapper<'a> {
 			Event::Button(btn@input::Button::Left, press) |
 			Event::Button(btn@input::Button::Right, press) |
 			Event::Button(btn@input::Button::Pad, press) => {
-				button!(self, pad_left, at, btn, press)?;
+				button!(state, pad_left, at, btn, press)?;
 			}
 
 			Event::Button(btn@input::Button::Track, press) => {
-				button!(self, pad_right, at, btn, press)?;
+				button!(state, pad_right, at, btn, press)?;
 			}
 
 			Event::Button(btn@input::Button::TriggerLeft, press) => {
-				button!(self, trigger_left, at, btn, press)?;
+				button!(state, trigger_left, at, btn, press)?;
 			}
 
 			Event::Button(btn@input::Button::TriggerRight, press) => {
-				button!(self, trigger_right, at, btn, press)?;
+				button!(state, trigger_right, at, btn, press)?;
 			}
 
 			_ => ()
 		}
 
-		self.device.synchronize()?;
+		state.device.synchronize()?;
 
 		Ok(())
 	}
+
+	/// Get the haptic feedback requested through the devices, with the
+	/// controller to play it on.
+	pub fn feedback(&mut self) -> Res<Vec<(Id, Haptic)>> {
+		let mut result = Vec::new();
+
+		for (&id, state) in &mut self.controllers {
+			result.extend(state.device.feedback()?.into_iter().map(|haptic| (id, haptic)));
+		}
+
+		Ok(result)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::rc::Rc;
+	use std::cell::RefCell;
+	use std::time::{Instant, Duration};
+	use uinput;
+	use controller::{Controller, button};
+	use controller::transport::Emulator;
+	use {Result as Res};
+	use config;
+	use input;
+	use super::Mapper;
+	use super::super::Device;
+
+	/// Records the events of every device it's cloned into.
+	#[derive(Clone, Default)]
+	struct Recorder {
+		events: Rc<RefCell<Vec<(uinput::Event, i32)>>>,
+	}
+
+	impl Device for Recorder {
+		fn send(&mut self, event: uinput::Event, value: i32) -> Res<()> {
+			self.events.borrow_mut().push((event, value));
+			Ok(())
+		}
+
+		fn synchronize(&mut self) -> Res<()> {
+			Ok(())
+		}
+	}
+
+	fn play(emulator: Emulator) -> Vec<(uinput::Event, i32)> {
+		let config         = config::load(concat!(env!("CARGO_MANIFEST_DIR"), "/vdf/tests/desktop.vdf")).unwrap();
+		let recorder       = Recorder::default();
+		let device         = recorder.clone();
+		let mut mapper     = Mapper::with_devices(&config, move || Ok(device.clone())).unwrap();
+		let mut controller = Controller::new(emulator, 0x1102).unwrap();
+		let mut state      = input::State::default();
+		let     start      = Instant::now();
+
+		// Events are timed by the script, not by how fast it's played.
+		while let Ok(report) = controller.state(Duration::from_secs(0)) {
+			let at = start + controller.transport().now();
+
+			for event in state.update(report) {
+				mapper.event(at, 0, event).unwrap();
+			}
+		}
+
+		let events = recorder.events.borrow().clone();
+		events
+	}
+
+	#[test]
+	fn button() {
+		let events = play(Emulator::new()
+			.at(Duration::from_millis(10)).press(button::A)
+			.at(Duration::from_millis(20)).release(button::A));
+
+		let enter = uinput::Event::from(uinput::event::keyboard::Key::Enter);
+		assert_eq!(vec![(enter, 1), (enter, 0)], events);
+	}
+
+	#[test]
+	fn switch() {
+		let events = play(Emulator::new()
+			.at(Duration::from_millis(10)).press(button::LEFT_BUMPER)
+			.at(Duration::from_millis(20)).release(button::LEFT_BUMPER));
+
+		let left = uinput::Event::from(uinput::event::keyboard::Key::Left);
+		assert_eq!(vec![(left, 1), (left, 0)], events);
+	}
+
+	#[test]
+	fn disconnect() {
+		let events = play(Emulator::new()
+			.at(Duration::from_millis(10)).press(button::A)
+			.at(Duration::from_millis(20)).power(false));
+
+		let enter = uinput::Event::from(uinput::event::keyboard::Key::Enter);
+		assert_eq!(vec![(enter, 1), (enter, 0)], events);
+	}
+
+	#[test]
+	fn controllers() {
+		let config     = config::load(concat!(env!("CARGO_MANIFEST_DIR"), "/vdf/tests/desktop.vdf")).unwrap();
+		let recorder   = Recorder::default();
+		let device     = recorder.clone();
+		let mut mapper = Mapper::with_devices(&config, move || Ok(device.clone())).unwrap();
+		let     now    = Instant::now();
+
+		mapper.event(now, 0, input::Event::Button(input::Button::A, true)).unwrap();
+		mapper.event(now, 1, input::Event::Button(input::Button::A, true)).unwrap();
+		mapper.event(now, 0, input::Event::Disconnected).unwrap();
+
+		// Only what the disconnected controller was holding is released.
+		let enter = uinput::Event::from(uinput::event::keyboard::Key::Enter);
+		assert_eq!(vec![(enter, 1), (enter, 1), (enter, 0)], *recorder.events.borrow());
+		assert_eq!(vec![1], mapper.controllers.keys().cloned().collect::<Vec<_>>());
+	}
 }
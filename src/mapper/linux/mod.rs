@@ -2,7 +2,7 @@
 mod util;
 
 mod traits;
-pub use self::traits::Button;
+pub use self::traits::{Button, Device};
 
 mod mapper;
 pub use self::mapper::Mapper;
@@ -10,6 +10,14 @@ pub use self::mapper::Mapper;
 mod preset;
 pub use self::preset::Preset;
 
+mod ffi;
+
+pub mod rumble;
+pub use self::rumble::Rumble;
+
+mod device;
+pub use self::device::{Builder, Virtual};
+
 mod switch;
 pub use self::switch::Switch;
 
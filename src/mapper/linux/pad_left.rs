@@ -1,12 +1,11 @@
 use std::iter;
 use std::time::Instant;
 use std::collections::HashSet;
-use uinput;
 use {Result as Res, Error};
 use input;
 use config::{Binding, Group, group};
 use util::iter;
-use super::{util, Button};
+use super::{util, Button, Device};
 
 #[derive(Debug)]
 pub struct PadLeft<'a> {
@@ -41,7 +40,7 @@ impl<'a> PadLeft<'a> {
 }
 
 impl<'a> Button for PadLeft<'a> {
-	fn button(&mut self, device: &mut uinput::Device, _at: Instant, button: input::Button, press: bool) -> Res<HashSet<&Binding>> {
+	fn button(&mut self, device: &mut Device, _at: Instant, button: input::Button, press: bool) -> Res<HashSet<&Binding>> {
 		let bindings = if let Some(bindings) = self.bindings() {
 			match bindings {
 				&group::Bindings::FourButtons { ref a, ref b, ref x, ref y } => {
@@ -0,0 +1,248 @@
+//! Force feedback on the virtual device.
+//!
+//! Games upload rumble effects and then play or stop them, the strong motor is
+//! emulated on the left pad and the weak one on the right pad.
+
+use std::io;
+use std::mem;
+use std::cmp;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use std::os::unix::io::RawFd;
+use libc::{self, c_int, c_ulong};
+use controller::haptic::{Pattern, Side};
+use {Result as Res};
+use input::Haptic;
+use super::ffi;
+
+/// Maximum number of effects that can be uploaded at once.
+pub const EFFECTS: u32 = 16;
+
+/// Interval between pulses while a motor is on.
+const INTERVAL: u64 = 16;
+
+/// Maximum number of times an effect is played in a row.
+const REPEAT: u32 = 64;
+
+/// An uploaded rumble effect.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Effect {
+	/// Magnitude of the strong motor.
+	pub strong: u16,
+
+	/// Magnitude of the weak motor.
+	pub weak: u16,
+
+	/// How long the effect lasts, zero means until stopped.
+	pub length: Duration,
+
+	/// How long to wait before playing.
+	pub delay: Duration,
+}
+
+impl Effect {
+	/// The pulse trains playing the effect the given number of times, effects
+	/// without a length loop until stopped.
+	pub fn pattern(&self, count: u32) -> Pattern {
+		let interval = Duration::from_millis(INTERVAL);
+		let mut pattern = Pattern::new();
+		let mut at      = self.delay;
+
+		loop {
+			if self.strong > 0 {
+				pattern = pattern.pulse(at, Side::Left, amplitude(self.strong), 8_000, 2);
+			}
+
+			if self.weak > 0 {
+				pattern = pattern.pulse(at, Side::Right, amplitude(self.weak), 8_000, 2);
+			}
+
+			at += interval;
+
+			if at >= self.delay + self.length {
+				break;
+			}
+		}
+
+		if self.length == Duration::from_secs(0) {
+			pattern.repeat(interval, None)
+		}
+		else {
+			pattern.repeat(self.delay + self.length, Some(cmp::min(cmp::max(count, 1), REPEAT)))
+		}
+	}
+}
+
+fn amplitude(magnitude: u16) -> u16 {
+	(magnitude as u32 * 1024 / 65535) as u16
+}
+
+/// Keeps track of the uploaded and playing effects.
+#[derive(Default, Debug)]
+pub struct Rumble {
+	effects: HashMap<i16, Effect>,
+	playing: HashSet<i16>,
+}
+
+impl Rumble {
+	pub fn new() -> Rumble {
+		Rumble::default()
+	}
+
+	/// Handle an event read from the device, returns what has to be done on
+	/// the pads.
+	pub fn handle(&mut self, fd: RawFd, kind: u16, code: u16, value: i32) -> Res<Option<Haptic>> {
+		match kind {
+			ffi::EV_UINPUT if code == ffi::FF_UPLOAD =>
+				upload(fd, value as u32, |id, effect| self.upload(id, effect)).map(|_| None),
+
+			ffi::EV_UINPUT if code == ffi::FF_ERASE =>
+				erase(fd, value as u32, |id| self.erase(id)),
+
+			ffi::EV_FF =>
+				Ok(self.play(code as i16, value)),
+
+			_ =>
+				Ok(None)
+		}
+	}
+
+	/// Store an uploaded effect, replacing the one with the same id.
+	pub fn upload(&mut self, id: i16, effect: Effect) {
+		self.effects.insert(id, effect);
+	}
+
+	/// Forget an effect, stopping it if it's playing; effects are erased when
+	/// the device is closed.
+	pub fn erase(&mut self, id: i16) -> Option<Haptic> {
+		self.effects.remove(&id);
+		self.stop(id)
+	}
+
+	/// Play an effect the given number of times, or stop it.
+	pub fn play(&mut self, id: i16, count: i32) -> Option<Haptic> {
+		if count <= 0 {
+			return self.stop(id);
+		}
+
+		let effect = match self.effects.get(&id) {
+			Some(effect) => effect,
+			None         => return None,
+		};
+
+		self.playing.insert(id);
+		Some(Haptic::Play(id as u32, effect.pattern(count as u32)))
+	}
+
+	fn stop(&mut self, id: i16) -> Option<Haptic> {
+		if self.playing.remove(&id) {
+			Some(Haptic::Stop(id as u32))
+		}
+		else {
+			None
+		}
+	}
+}
+
+fn ioctl<T>(fd: RawFd, request: c_ulong, value: &mut T) -> Res<()> {
+	if unsafe { libc::ioctl(fd, request as _, value as *mut T) } < 0 {
+		Err(io::Error::last_os_error().into())
+	}
+	else {
+		Ok(())
+	}
+}
+
+fn upload<F: FnOnce(i16, Effect)>(fd: RawFd, request: u32, func: F) -> Res<()> {
+	let mut upload: ffi::uinput_ff_upload = unsafe { mem::zeroed() };
+	upload.request_id = request;
+
+	ioctl(fd, ffi::begin_upload(), &mut upload)?;
+
+	if upload.effect.kind == ffi::FF_RUMBLE {
+		func(upload.effect.id, Effect {
+			strong: upload.effect.u.strong_magnitude,
+			weak:   upload.effect.u.weak_magnitude,
+			length: Duration::from_millis(upload.effect.replay.length as u64),
+			delay:  Duration::from_millis(upload.effect.replay.delay as u64),
+		});
+
+		upload.retval = 0;
+	}
+	else {
+		upload.retval = -(ffi::EINVAL as c_int);
+	}
+
+	ioctl(fd, ffi::end_upload(), &mut upload)
+}
+
+fn erase<F: FnOnce(i16) -> Option<Haptic>>(fd: RawFd, request: u32, func: F) -> Res<Option<Haptic>> {
+	let mut erase: ffi::uinput_ff_erase = unsafe { mem::zeroed() };
+	erase.request_id = request;
+
+	ioctl(fd, ffi::begin_erase(), &mut erase)?;
+	let result = func(erase.effect_id as i16);
+
+	erase.retval = 0;
+	ioctl(fd, ffi::end_erase(), &mut erase)?;
+
+	Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+	use input::Haptic;
+	use super::{Effect, Rumble};
+
+	#[test]
+	fn pattern() {
+		let effect = Effect {
+			strong: 65535,
+			weak:   0,
+			length: Duration::from_millis(0),
+			delay:  Duration::from_millis(0),
+		};
+
+		// Effects without a length are a single looping pulse.
+		assert_eq!(1, effect.pattern(u32::max_value()).pulses().len());
+		assert_eq!(1024, effect.pattern(1).pulses()[0].amplitude);
+
+		let effect = Effect { weak: 65535, length: Duration::from_millis(100), .. effect };
+		assert_eq!(14, effect.pattern(u32::max_value()).pulses().len());
+	}
+
+	fn stopped(haptic: Option<Haptic>) -> Option<u32> {
+		match haptic {
+			Some(Haptic::Stop(key)) => Some(key),
+			_                       => None,
+		}
+	}
+
+	#[test]
+	fn effects() {
+		let effect = Effect {
+			strong: 65535,
+			weak:   0,
+			length: Duration::from_millis(0),
+			delay:  Duration::from_millis(0),
+		};
+
+		let mut rumble = Rumble::new();
+		rumble.upload(0, effect);
+		rumble.upload(1, Effect { strong: 0, weak: 65535, .. effect });
+
+		assert!(rumble.play(0, 1).is_some());
+		assert!(rumble.play(1, 1).is_some());
+		assert!(rumble.play(2, 1).is_none());
+
+		// Every effect is stopped on its own.
+		assert_eq!(Some(0), stopped(rumble.play(0, 0)));
+		assert_eq!(None, stopped(rumble.play(0, 0)));
+
+		// Erasing a playing effect, like closing the device does, stops it.
+		assert_eq!(Some(1), stopped(rumble.erase(1)));
+		assert_eq!(None, stopped(rumble.erase(0)));
+		assert!(rumble.play(1, 1).is_none());
+	}
+}
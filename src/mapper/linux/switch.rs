@@ -1,12 +1,11 @@
 use std::time::Instant;
 use std::collections::HashSet;
 use std::iter;
-use uinput;
 use {Result as Res};
 use input;
 use config::{self, Binding, Config, preset};
 use util::iter;
-use super::{Preset, Button, util};
+use super::{Preset, Button, Device, util};
 
 pub struct Switch<'a> {
 	config: &'a preset::Preset,
@@ -21,7 +20,7 @@ impl<'a> Switch<'a> {
 }
 
 impl<'a> Button for Switch<'a> {
-	fn button(&mut self, device: &mut uinput::Device, _at: Instant, button: input::Button, press: bool) -> Res<HashSet<&Binding>> {
+	fn button(&mut self, device: &mut Device, _at: Instant, button: input::Button, press: bool) -> Res<HashSet<&Binding>> {
 		let bindings = if let Some(button) = convert(button) {
 			if let Some(bindings) = self.config.bindings.get(&button) {
 				iter(bindings.iter())
@@ -2,11 +2,32 @@ use std::time::Instant;
 use std::collections::HashSet;
 use uinput;
 use {Result as Res};
-use input;
+use input::{self, Haptic};
 use config::Binding;
 
+/// Where the mapped events are sent.
+pub trait Device {
+	fn send(&mut self, event: uinput::Event, value: i32) -> Res<()>;
+	fn synchronize(&mut self) -> Res<()>;
+
+	/// The haptic feedback requested through the device.
+	fn feedback(&mut self) -> Res<Vec<Haptic>> {
+		Ok(Vec::new())
+	}
+}
+
+impl Device for uinput::Device {
+	fn send(&mut self, event: uinput::Event, value: i32) -> Res<()> {
+		Ok(uinput::Device::send(self, event, value)?)
+	}
+
+	fn synchronize(&mut self) -> Res<()> {
+		Ok(uinput::Device::synchronize(self)?)
+	}
+}
+
 pub trait Button {
-	fn button(&mut self, device: &mut uinput::Device, at: Instant, button: input::Button, press: bool) -> Res<HashSet<&Binding>>;
+	fn button(&mut self, device: &mut Device, at: Instant, button: input::Button, press: bool) -> Res<HashSet<&Binding>>;
 }
 
 pub trait Shift {
@@ -2,6 +2,7 @@ use std::collections::HashSet;
 use uinput;
 use {Result as Res};
 use config::binding::{self, Binding};
+use super::Device;
 
 macro_rules! switch {
 	($mapper:expr, $button:expr) => (
@@ -42,13 +43,13 @@ macro_rules! button {
 	});
 }
 
-pub fn button<'a, T: Iterator<Item = &'a Binding>>(device: &mut uinput::Device, events: T, press: bool) -> Res<HashSet<&'a Binding>> {
+pub fn button<'a, T: Iterator<Item = &'a Binding>>(device: &mut Device, events: T, press: bool) -> Res<HashSet<&'a Binding>> {
 	events.map(|binding|
 		if let &Binding::Action(..) = binding {
 			Ok(binding)
 		}
 		else {
-			device.send(binding, if press { 1 } else { 0 })?;
+			device.send(binding.into(), if press { 1 } else { 0 })?;
 			Ok(binding)
 		}).collect()
 }
@@ -0,0 +1,23 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use libc;
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn interrupt(_: libc::c_int) {
+	INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Catch the termination signals, so the controllers can be given back
+/// before exiting.
+pub fn install() {
+	for &signal in &[libc::SIGINT, libc::SIGTERM, libc::SIGHUP] {
+		unsafe {
+			libc::signal(signal, interrupt as libc::sighandler_t);
+		}
+	}
+}
+
+/// Check if a termination signal has been received.
+pub fn is_interrupted() -> bool {
+	INTERRUPTED.load(Ordering::SeqCst)
+}
@@ -1,15 +1,3 @@
-macro_rules! wait {
-	($body:expr, $time:expr) => (
-		if let Ok(value) = $body {
-			value
-		}
-		else {
-			thread::sleep(Duration::from_millis($time));
-			continue;
-		}
-	);
-}
-
 macro_rules! end {
 	($body:expr) => (
 		if let Ok(value) = $body {
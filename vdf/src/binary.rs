@@ -0,0 +1,284 @@
+//! Binary KeyValues.
+//!
+//! Every entry starts with a type tag and a NUL terminated key, tables end
+//! with `0x08`; values keep their `Kind` so they're written back with the
+//! same type, and statements can't be written.
+
+use std::io::{self, Read, Write, BufReader, BufWriter};
+use std::fs::File;
+use std::path::Path;
+use byteorder::{ReadBytesExt, WriteBytesExt, LittleEndian};
+use entry::{Entry, Table, Value, Kind};
+use {Result as Res, Error};
+
+const TABLE:       u8 = 0x00;
+const STRING:      u8 = 0x01;
+const INT32:       u8 = 0x02;
+const FLOAT:       u8 = 0x03;
+const POINTER:     u8 = 0x04;
+const WIDE_STRING: u8 = 0x05;
+const COLOR:       u8 = 0x06;
+const UINT64:      u8 = 0x07;
+const END:         u8 = 0x08;
+const INT64:       u8 = 0x0a;
+
+/// Some files end tables with this instead.
+const ALTERNATE_END: u8 = 0x0b;
+
+/// Load a table from the given path.
+pub fn open<P: AsRef<Path>>(path: P) -> Res<Table> {
+	load(BufReader::new(try!(File::open(path))))
+}
+
+/// Load a table from the given stream.
+pub fn load<R: Read>(mut stream: R) -> Res<Table> {
+	table(&mut stream, true)
+}
+
+/// Save a table to the given path.
+pub fn save<P: AsRef<Path>>(path: P, table: &Table) -> Res<()> {
+	write(BufWriter::new(try!(File::create(path))), table)
+}
+
+/// Write a table to the given stream.
+pub fn write<W: Write>(mut stream: W, table: &Table) -> Res<()> {
+	for (key, value) in table.entries() {
+		try!(entry(&mut stream, key, value));
+	}
+
+	try!(stream.write_u8(END));
+	try!(stream.flush());
+
+	Ok(())
+}
+
+fn table<R: Read>(stream: &mut R, top: bool) -> Res<Table> {
+	let mut table = Table::new();
+
+	loop {
+		let kind = match stream.read_u8() {
+			Ok(kind) =>
+				kind,
+
+			// The top level may end without a terminator.
+			Err(ref err) if top && err.kind() == io::ErrorKind::UnexpectedEof =>
+				break,
+
+			Err(err) =>
+				return Err(eof(err)),
+		};
+
+		if kind == END || kind == ALTERNATE_END {
+			break;
+		}
+
+		let key = try!(string(stream));
+
+		match kind {
+			TABLE =>
+				table.push(key, try!(self::table(stream, false))),
+
+			STRING =>
+				table.push(key, Value::from(try!(string(stream)))),
+
+			WIDE_STRING =>
+				table.push(key, Value::new(try!(wide(stream)), Kind::WideString)),
+
+			INT32 =>
+				table.push(key, Value::from(try!(stream.read_i32::<LittleEndian>().map_err(eof)))),
+
+			FLOAT =>
+				table.push(key, Value::from(try!(stream.read_f32::<LittleEndian>().map_err(eof)))),
+
+			POINTER =>
+				table.push(key, Value::new(try!(stream.read_i32::<LittleEndian>().map_err(eof)).to_string(), Kind::Pointer)),
+
+			COLOR =>
+				table.push(key, Value::new(try!(stream.read_u32::<LittleEndian>().map_err(eof)).to_string(), Kind::Color)),
+
+			UINT64 =>
+				table.push(key, Value::from(try!(stream.read_u64::<LittleEndian>().map_err(eof)))),
+
+			INT64 =>
+				table.push(key, Value::from(try!(stream.read_i64::<LittleEndian>().map_err(eof)))),
+
+			_ =>
+				return Err(Error::Parse)
+		}
+	}
+
+	Ok(table)
+}
+
+fn eof<E: Into<Error>>(err: E) -> Error {
+	match err.into() {
+		Error::Io(ref err) if err.kind() == io::ErrorKind::UnexpectedEof =>
+			Error::Eof,
+
+		err =>
+			err
+	}
+}
+
+fn string<R: Read>(stream: &mut R) -> Res<String> {
+	let mut buffer = Vec::new();
+
+	loop {
+		match try!(stream.read_u8().map_err(eof)) {
+			0    => break,
+			byte => buffer.push(byte),
+		}
+	}
+
+	String::from_utf8(buffer).map_err(|_| Error::Parse)
+}
+
+fn wide<R: Read>(stream: &mut R) -> Res<String> {
+	let mut buffer = Vec::new();
+
+	loop {
+		match try!(stream.read_u16::<LittleEndian>().map_err(eof)) {
+			0    => break,
+			unit => buffer.push(unit),
+		}
+	}
+
+	String::from_utf16(&buffer).map_err(|_| Error::Parse)
+}
+
+fn entry<W: Write>(stream: &mut W, key: &str, value: &Entry) -> Res<()> {
+	fn header<W: Write>(stream: &mut W, kind: u8, key: &str) -> Res<()> {
+		try!(stream.write_u8(kind));
+		try!(stream.write_all(key.as_bytes()));
+		try!(stream.write_u8(0));
+
+		Ok(())
+	}
+
+	fn number<T: ::std::str::FromStr>(value: &Value) -> Res<T> {
+		value.parse().map_err(|_| Error::Message(format!("{:?} is not a valid {:?}", &**value, value.kind())))
+	}
+
+	match value {
+		&Entry::Table(ref table) => {
+			try!(header(stream, TABLE, key));
+
+			for (key, value) in table.entries() {
+				try!(entry(stream, key, value));
+			}
+
+			try!(stream.write_u8(END));
+		}
+
+		&Entry::Array(ref array) =>
+			for value in array.iter() {
+				try!(entry(stream, key, value));
+			},
+
+		// Binary files have no statements, they'd be read back as values.
+		&Entry::Statement(..) =>
+			return Err(Error::Message(format!("{:?} is a statement, binary files can't contain them", key))),
+
+		&Entry::Value(ref value) =>
+			match value.kind() {
+				Kind::String => {
+					try!(header(stream, STRING, key));
+					try!(stream.write_all(value.as_bytes()));
+					try!(stream.write_u8(0));
+				}
+
+				Kind::WideString => {
+					try!(header(stream, WIDE_STRING, key));
+
+					for unit in value.encode_utf16().chain(Some(0)) {
+						try!(stream.write_u16::<LittleEndian>(unit));
+					}
+				}
+
+				Kind::Int32 => {
+					try!(header(stream, INT32, key));
+					try!(stream.write_i32::<LittleEndian>(try!(number(value))));
+				}
+
+				Kind::Float => {
+					try!(header(stream, FLOAT, key));
+					try!(stream.write_f32::<LittleEndian>(try!(number(value))));
+				}
+
+				Kind::Pointer => {
+					try!(header(stream, POINTER, key));
+					try!(stream.write_i32::<LittleEndian>(try!(number(value))));
+				}
+
+				Kind::Color => {
+					try!(header(stream, COLOR, key));
+					try!(stream.write_u32::<LittleEndian>(try!(number(value))));
+				}
+
+				Kind::UInt64 => {
+					try!(header(stream, UINT64, key));
+					try!(stream.write_u64::<LittleEndian>(try!(number(value))));
+				}
+
+				Kind::Int64 => {
+					try!(header(stream, INT64, key));
+					try!(stream.write_i64::<LittleEndian>(try!(number(value))));
+				}
+			},
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use entry::{Entry, Table, Kind};
+	use reader::Reader;
+	use Error;
+
+	// A `shortcuts.vdf` with a single non-Steam game.
+	const SHORTCUTS: &'static [u8] =
+		b"\x00shortcuts\x00\
+		  \x000\x00\
+		  \x02appid\x00\xd2\x02\x96\x49\
+		  \x01AppName\x00Game\x00\
+		  \x01Exe\x00\"/usr/bin/game\"\x00\
+		  \x03scale\x00\x00\x00\xc0\x3f\
+		  \x07size\x00\x00\x00\x00\x00\x01\x00\x00\x00\
+		  \x00tags\x00\
+		  \x010\x00favorite\x00\
+		  \x08\
+		  \x08\
+		  \x08\
+		  \x08";
+
+	#[test]
+	fn shortcuts() {
+		let table = super::load(SHORTCUTS).unwrap();
+		let entry = Entry::Table(table.clone());
+
+		assert_eq!(Some(1234567890), entry.lookup("shortcuts.0.appid").unwrap().to::<i32>());
+		assert_eq!(Kind::Int32, entry.lookup("shortcuts.0.appid").unwrap().as_value().unwrap().kind());
+		assert_eq!("Game", entry.lookup("shortcuts.0.AppName").unwrap().as_str().unwrap());
+		assert_eq!(Some(1.5), entry.lookup("shortcuts.0.scale").unwrap().to::<f32>());
+		assert_eq!(Some(1 << 32), entry.lookup("shortcuts.0.size").unwrap().to::<u64>());
+		assert_eq!("favorite", entry.lookup("shortcuts.0.tags.0").unwrap().as_str().unwrap());
+
+		let mut output = Vec::new();
+		super::write(&mut output, &table).unwrap();
+		assert_eq!(SHORTCUTS, &output[..]);
+
+		// The kind isn't part of equality, the same tree from text is equal.
+		let text = Table::load(&mut Reader::from(&b"shortcuts { 0 { appid 1234567890 AppName Game Exe \"\\\"/usr/bin/game\\\"\" \
+			scale 1.5 size 4294967296 tags { 0 favorite } } }"[..])).unwrap();
+		assert_eq!(text, table);
+
+		let statement = Table::load(&mut Reader::from(&b"key #statement"[..])).unwrap();
+		assert!(super::write(&mut Vec::new(), &statement).is_err());
+
+		match super::load(&SHORTCUTS[.. 20]) {
+			Err(Error::Eof) => (),
+			_               => panic!(),
+		}
+	}
+}
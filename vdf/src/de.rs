@@ -0,0 +1,353 @@
+//! Deserialization with serde.
+//!
+//! Tables map to structs and maps, repeated keys to sequences, and values
+//! are parsed into the requested type like `Entry::to` does.
+
+use std::fmt::Display;
+use std::io::Read;
+use serde::de::{self, Deserialize, DeserializeOwned, Visitor, IntoDeserializer};
+use entry::{Entry, Table, Parse};
+use reader::Reader;
+use {Result as Res, Error};
+
+impl de::Error for Error {
+	fn custom<T: Display>(message: T) -> Self {
+		Error::Message(message.to_string())
+	}
+}
+
+/// Deserialize a type from an entry.
+pub fn from_entry<'de, T: Deserialize<'de>>(entry: &'de Entry) -> Res<T> {
+	T::deserialize(Deserializer::new(entry))
+}
+
+/// Deserialize a type from the given stream.
+pub fn from_reader<R: Read, T: DeserializeOwned>(stream: R) -> Res<T> {
+	let entry = Entry::Table(try!(Table::load(&mut Reader::from(stream))));
+	from_entry(&entry)
+}
+
+/// Deserialize a type from the given string.
+pub fn from_str<T: DeserializeOwned>(string: &str) -> Res<T> {
+	from_reader(string.as_bytes())
+}
+
+/// A deserializer over an entry.
+pub struct Deserializer<'de> {
+	entry: &'de Entry,
+}
+
+impl<'de> Deserializer<'de> {
+	/// Create a deserializer for the entry.
+	pub fn new(entry: &'de Entry) -> Deserializer<'de> {
+		Deserializer {
+			entry: entry,
+		}
+	}
+
+	fn string(&self) -> Res<&'de str> {
+		self.entry.as_str().ok_or_else(||
+			de::Error::invalid_type(unexpected(self.entry), &"a value"))
+	}
+
+	fn parse<T: Parse>(&self, expected: &'static str) -> Res<T> {
+		let string = try!(self.string());
+
+		T::parse(string).ok_or_else(||
+			de::Error::invalid_value(de::Unexpected::Str(string), &expected))
+	}
+}
+
+fn unexpected(entry: &Entry) -> de::Unexpected {
+	match entry {
+		&Entry::Table(..) =>
+			de::Unexpected::Map,
+
+		&Entry::Array(..) =>
+			de::Unexpected::Seq,
+
+		&Entry::Statement(ref value) =>
+			de::Unexpected::Str(value),
+
+		&Entry::Value(ref value) =>
+			de::Unexpected::Str(value),
+	}
+}
+
+macro_rules! parse {
+	($($method:ident => $visit:ident ($ty:ty)),* $(,)*) => ($(
+		fn $method<V: Visitor<'de>>(self, visitor: V) -> Res<V::Value> {
+			visitor.$visit(try!(self.parse::<$ty>(stringify!($ty))))
+		}
+	)*);
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer<'de> {
+	type Error = Error;
+
+	fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Res<V::Value> {
+		match self.entry {
+			&Entry::Table(..) =>
+				self.deserialize_map(visitor),
+
+			&Entry::Array(..) =>
+				self.deserialize_seq(visitor),
+
+			_ =>
+				self.deserialize_str(visitor),
+		}
+	}
+
+	parse! {
+		deserialize_bool => visit_bool(bool),
+
+		deserialize_i8  => visit_i8(i8),
+		deserialize_i16 => visit_i16(i16),
+		deserialize_i32 => visit_i32(i32),
+		deserialize_i64 => visit_i64(i64),
+
+		deserialize_u8  => visit_u8(u8),
+		deserialize_u16 => visit_u16(u16),
+		deserialize_u32 => visit_u32(u32),
+		deserialize_u64 => visit_u64(u64),
+
+		deserialize_f32 => visit_f32(f32),
+		deserialize_f64 => visit_f64(f64),
+	}
+
+	fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Res<V::Value> {
+		let string    = try!(self.string());
+		let mut chars = string.chars();
+
+		match (chars.next(), chars.next()) {
+			(Some(ch), None) =>
+				visitor.visit_char(ch),
+
+			_ =>
+				Err(de::Error::invalid_value(de::Unexpected::Str(string), &"a character"))
+		}
+	}
+
+	fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Res<V::Value> {
+		visitor.visit_borrowed_str(try!(self.string()))
+	}
+
+	fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Res<V::Value> {
+		self.deserialize_str(visitor)
+	}
+
+	fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Res<V::Value> {
+		visitor.visit_borrowed_bytes(try!(self.string()).as_bytes())
+	}
+
+	fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Res<V::Value> {
+		self.deserialize_bytes(visitor)
+	}
+
+	fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Res<V::Value> {
+		// Missing keys are handled by serde, anything present is `Some`.
+		visitor.visit_some(self)
+	}
+
+	fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Res<V::Value> {
+		visitor.visit_unit()
+	}
+
+	fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Res<V::Value> {
+		self.deserialize_unit(visitor)
+	}
+
+	fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Res<V::Value> {
+		visitor.visit_newtype_struct(self)
+	}
+
+	fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Res<V::Value> {
+		// A single entry is a sequence of one, like `Entry::as_slice`.
+		let entries = self.entry.as_slice().unwrap();
+		visitor.visit_seq(de::value::SeqDeserializer::new(entries.iter().map(Deserializer::new)))
+	}
+
+	fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Res<V::Value> {
+		self.deserialize_seq(visitor)
+	}
+
+	fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize, visitor: V) -> Res<V::Value> {
+		self.deserialize_seq(visitor)
+	}
+
+	fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Res<V::Value> {
+		match self.entry {
+			&Entry::Table(ref table) =>
+				visitor.visit_map(de::value::MapDeserializer::new(table.iter().map(|(key, value)|
+					(&key[..], Deserializer::new(value))))),
+
+			entry =>
+				Err(de::Error::invalid_type(unexpected(entry), &"a table"))
+		}
+	}
+
+	fn deserialize_struct<V: Visitor<'de>>(self, _name: &'static str, _fields: &'static [&'static str], visitor: V) -> Res<V::Value> {
+		self.deserialize_map(visitor)
+	}
+
+	fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Res<V::Value> {
+		match self.entry {
+			&Entry::Table(ref table) if table.len() == 1 => {
+				let (key, value) = table.iter().next().unwrap();
+				visitor.visit_enum(Enum { variant: key, value: value })
+			}
+
+			&Entry::Table(..) | &Entry::Array(..) =>
+				Err(de::Error::invalid_type(unexpected(self.entry), &"a variant")),
+
+			_ =>
+				visitor.visit_enum(try!(self.string()).into_deserializer())
+		}
+	}
+
+	fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Res<V::Value> {
+		self.deserialize_str(visitor)
+	}
+
+	fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Res<V::Value> {
+		visitor.visit_unit()
+	}
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Deserializer<'de> {
+	type Deserializer = Self;
+
+	fn into_deserializer(self) -> Self {
+		self
+	}
+}
+
+/// A variant written as a table with a single key.
+struct Enum<'de> {
+	variant: &'de str,
+	value:   &'de Entry,
+}
+
+impl<'de> de::EnumAccess<'de> for Enum<'de> {
+	type Error   = Error;
+	type Variant = Deserializer<'de>;
+
+	fn variant_seed<V: de::DeserializeSeed<'de>>(self, seed: V) -> Res<(V::Value, Self::Variant)> {
+		let variant = try!(seed.deserialize(de::value::BorrowedStrDeserializer::<Error>::new(self.variant)));
+		Ok((variant, Deserializer::new(self.value)))
+	}
+}
+
+impl<'de> de::VariantAccess<'de> for Deserializer<'de> {
+	type Error = Error;
+
+	fn unit_variant(self) -> Res<()> {
+		Ok(())
+	}
+
+	fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Res<T::Value> {
+		seed.deserialize(self)
+	}
+
+	fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Res<V::Value> {
+		de::Deserializer::deserialize_seq(self, visitor)
+	}
+
+	fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V) -> Res<V::Value> {
+		de::Deserializer::deserialize_map(self, visitor)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs::File;
+	use std::collections::BTreeMap;
+	use {Entry, Table, Reader, from_reader, from_str, to_entry, to_string};
+
+	#[derive(Deserialize, Debug)]
+	struct Config {
+		controller_mappings: Mappings,
+	}
+
+	#[derive(Deserialize, Debug)]
+	struct Mappings {
+		version: f32,
+		group:   Vec<Group>,
+	}
+
+	#[derive(Deserialize, Debug)]
+	struct Group {
+		id:       u32,
+		mode:     String,
+		settings: Option<BTreeMap<String, String>>,
+	}
+
+	#[test]
+	fn config() {
+		let config: Config = from_reader(File::open("tests/desktop.vdf").unwrap()).unwrap();
+
+		assert_eq!(2.0, config.controller_mappings.version);
+		assert_eq!(9, config.controller_mappings.group.len());
+		assert_eq!("four_buttons", config.controller_mappings.group[0].mode);
+		assert_eq!(Some("0"), config.controller_mappings.group[1].settings.as_ref().unwrap().get("requires_click").map(|s| &s[..]));
+	}
+
+	#[derive(Serialize, Deserialize, PartialEq, Debug)]
+	#[serde(rename_all = "snake_case")]
+	enum Mode {
+		FourButtons,
+		Dpad,
+	}
+
+	#[derive(Serialize, Deserialize, PartialEq, Debug)]
+	struct Settings {
+		requires_click: bool,
+		sensitivity:    Option<u32>,
+	}
+
+	#[derive(Serialize, Deserialize, PartialEq, Debug)]
+	struct Binding {
+		mode:     Mode,
+		settings: Settings,
+		#[serde(default)]
+		click:    Vec<String>,
+	}
+
+	#[test]
+	fn round_trip() {
+		let binding: Binding = from_str(r#"
+			"mode"		"dpad"
+			"settings"
+			{
+				"requires_click"		"1"
+				"sensitivity"		"200"
+			}
+			"click"		"key_press A"
+			"click"		"key_press B"
+		"#).unwrap();
+
+		assert_eq!(Binding {
+			mode:     Mode::Dpad,
+			settings: Settings { requires_click: true, sensitivity: Some(200) },
+			click:    vec!["key_press A".into(), "key_press B".into()],
+		}, binding);
+
+		let output = to_string(&Binding {
+			mode:     Mode::FourButtons,
+			settings: Settings { requires_click: false, sensitivity: None },
+			click:    vec!["key_press C".into()],
+		}).unwrap();
+
+		assert_eq!("\"mode\"\t\t\"four_buttons\"\n\"settings\"\n{\n\t\"requires_click\"\t\t\"0\"\n}\n\"click\"\t\t\"key_press C\"\n", output);
+		assert_eq!(Mode::FourButtons, from_str::<Binding>(&output).unwrap().mode);
+
+		assert!(from_str::<Settings>("\"requires_click\"\t\t\"maybe\"").is_err());
+
+		// Typed values are equal to the same values read back from text.
+		let mut ids = BTreeMap::new();
+		ids.insert("appid", 1234567890i32);
+
+		let output = to_string(&ids).unwrap();
+		assert_eq!(Entry::Table(Table::load(&mut Reader::from(output.as_bytes())).unwrap()), to_entry(&ids).unwrap().unwrap());
+	}
+}
@@ -0,0 +1,660 @@
+//! Lossless VDF documents.
+//!
+//! Every node keeps the whitespace and comments around it, the quoting style
+//! and the conditional suffixes, so a document is written back exactly as it
+//! was parsed except for the edited nodes.
+
+use std::fmt;
+use std::io::{Read, Write};
+use std::fs::File;
+use std::path::Path;
+use parser;
+use writer::escape;
+use {Result as Res, Error};
+
+/// A key or value as it's written in the source.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Text {
+	raw: String,
+}
+
+impl Text {
+	/// Create a text, enclosed in quotes if asked for or needed.
+	pub fn new(value: &str, quoted: bool) -> Text {
+		let quoted = quoted || value.is_empty() || value.starts_with("//") || value.starts_with('[') ||
+			value.contains(|c| match c { ' ' | '\t' | '\n' | '\r' | '{' | '}' | '"' | '\\' => true, _ => false });
+
+		Text {
+			raw: if quoted { format!("\"{}\"", escape(value)) } else { value.into() }
+		}
+	}
+
+	/// The text as written in the source.
+	pub fn raw(&self) -> &str {
+		&self.raw
+	}
+
+	/// Check if the text is enclosed in quotes.
+	pub fn is_quoted(&self) -> bool {
+		self.raw.starts_with('"')
+	}
+
+	/// The unescaped value.
+	pub fn value(&self) -> String {
+		let inner = if self.is_quoted() { &self.raw[1 .. self.raw.len() - 1] } else { &self.raw[..] };
+		parser::string(inner.as_bytes()).map(|v| v.into_owned()).unwrap_or_else(|_| inner.into())
+	}
+}
+
+/// A conditional suffix, like `[$WIN32]`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Condition {
+	before: String,
+	raw:    String,
+}
+
+impl Condition {
+	/// The expression between the brackets.
+	pub fn expression(&self) -> &str {
+		&self.raw[1 .. self.raw.len() - 1]
+	}
+}
+
+/// A key/value pair.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Pair {
+	prefix:    String,
+	key:       Text,
+	separator: String,
+	value:     Text,
+	condition: Option<Condition>,
+	suffix:    String,
+}
+
+impl Pair {
+	/// The key.
+	pub fn key(&self) -> &Text {
+		&self.key
+	}
+
+	/// The value.
+	pub fn value(&self) -> &Text {
+		&self.value
+	}
+
+	/// The conditional suffix.
+	pub fn condition(&self) -> Option<&Condition> {
+		self.condition.as_ref()
+	}
+
+	/// Change the value, keeping the quoting style when possible.
+	pub fn set(&mut self, value: &str) {
+		self.value = Text::new(value, self.value.is_quoted());
+	}
+}
+
+/// A named group of nodes.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Group {
+	prefix:    String,
+	key:       Text,
+	condition: Option<Condition>,
+	open:      String,
+	opened:    String,
+	children:  Vec<Node>,
+	close:     String,
+	suffix:    String,
+}
+
+impl Group {
+	/// The key.
+	pub fn key(&self) -> &Text {
+		&self.key
+	}
+
+	/// The conditional suffix.
+	pub fn condition(&self) -> Option<&Condition> {
+		self.condition.as_ref()
+	}
+
+	/// The nodes in the group.
+	pub fn children(&self) -> &[Node] {
+		&self.children
+	}
+}
+
+/// A node in the document.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Node {
+	/// A key/value pair.
+	Pair(Pair),
+
+	/// A group.
+	Group(Group),
+}
+
+impl Node {
+	/// The key.
+	pub fn key(&self) -> &Text {
+		match self {
+			&Node::Pair(ref pair) =>
+				&pair.key,
+
+			&Node::Group(ref group) =>
+				&group.key,
+		}
+	}
+
+	fn prefix(&self) -> &str {
+		match self {
+			&Node::Pair(ref pair) =>
+				&pair.prefix,
+
+			&Node::Group(ref group) =>
+				&group.prefix,
+		}
+	}
+
+	fn suffix_mut(&mut self) -> &mut String {
+		match self {
+			&mut Node::Pair(ref mut pair) =>
+				&mut pair.suffix,
+
+			&mut Node::Group(ref mut group) =>
+				&mut group.suffix,
+		}
+	}
+}
+
+impl fmt::Display for Node {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			&Node::Pair(ref pair) => {
+				try!(write!(f, "{}{}{}{}", pair.prefix, pair.key.raw, pair.separator, pair.value.raw));
+
+				if let Some(ref condition) = pair.condition {
+					try!(write!(f, "{}{}", condition.before, condition.raw));
+				}
+
+				f.write_str(&pair.suffix)
+			}
+
+			&Node::Group(ref group) => {
+				try!(write!(f, "{}{}", group.prefix, group.key.raw));
+
+				if let Some(ref condition) = group.condition {
+					try!(write!(f, "{}{}", condition.before, condition.raw));
+				}
+
+				try!(write!(f, "{}{{{}", group.open, group.opened));
+
+				for child in &group.children {
+					try!(write!(f, "{}", child));
+				}
+
+				write!(f, "{}}}{}", group.close, group.suffix)
+			}
+		}
+	}
+}
+
+/// A lossless VDF document.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Document {
+	children: Vec<Node>,
+	trailing: String,
+	newline:  String,
+}
+
+impl Document {
+	/// Parse a document.
+	pub fn parse(source: &str) -> Res<Document> {
+		let mut lexer = Lexer { source: source, position: 0 };
+		let (children, trailing) = try!(lexer.nodes(true));
+
+		Ok(Document {
+			children: children,
+			trailing: trailing,
+			newline:  if source.contains("\r\n") { "\r\n".into() } else { "\n".into() },
+		})
+	}
+
+	/// Load a document from the given path.
+	pub fn load<P: AsRef<Path>>(path: P) -> Res<Document> {
+		let mut source = String::new();
+		try!(try!(File::open(path)).read_to_string(&mut source));
+
+		Document::parse(&source)
+	}
+
+	/// Write the document.
+	pub fn write<W: Write>(&self, mut output: W) -> Res<()> {
+		Ok(try!(write!(output, "{}", self)))
+	}
+
+	/// Save the document to the given path.
+	pub fn save<P: AsRef<Path>>(&self, path: P) -> Res<()> {
+		self.write(try!(File::create(path)))
+	}
+
+	/// The top level nodes.
+	pub fn nodes(&self) -> &[Node] {
+		&self.children
+	}
+
+	/// Lookup a node with a path, repeated keys are picked by index like in
+	/// `Entry::lookup`.
+	pub fn get<S: AsRef<str>>(&self, path: S) -> Option<&Node> {
+		let indices = match locate(&self.children, path.as_ref()) {
+			Some(indices) => indices,
+			None          => return None,
+		};
+
+		let mut current = &self.children;
+
+		for (i, &index) in indices.iter().enumerate() {
+			if i + 1 == indices.len() {
+				return current.get(index);
+			}
+
+			current = match current[index] {
+				Node::Group(ref group) => &group.children,
+				Node::Pair(..)         => return None,
+			};
+		}
+
+		None
+	}
+
+	/// Change the value of the pair at the path.
+	pub fn set<S: AsRef<str>>(&mut self, path: S, value: &str) -> Res<()> {
+		let (parent, index) = try!(self.parent(path.as_ref()));
+
+		match parent[index] {
+			Node::Pair(ref mut pair) =>
+				pair.set(value),
+
+			Node::Group(..) =>
+				return Err(Error::NotFound),
+		}
+
+		Ok(())
+	}
+
+	/// Add a pair at the end of the group at the path, an empty path is the
+	/// top level.
+	pub fn insert<S: AsRef<str>>(&mut self, path: S, key: &str, value: &str) -> Res<()> {
+		let newline = self.newline.clone();
+		let path    = path.as_ref();
+
+		let (children, indent) = if path.is_empty() {
+			(&mut self.children, String::new())
+		}
+		else {
+			let (parent, index) = try!(self.parent(path));
+
+			match parent[index] {
+				Node::Group(ref mut group) => {
+					let indent = if group.children.is_empty() {
+						format!("{}\t", indentation(&group.prefix))
+					}
+					else {
+						String::new()
+					};
+
+					if group.children.is_empty() && !group.opened.ends_with('\n') {
+						group.opened.push_str(&newline);
+					}
+
+					(&mut group.children, indent)
+				}
+
+				Node::Pair(..) =>
+					return Err(Error::NotFound)
+			}
+		};
+
+		// Follow the style of the last sibling.
+		let (indent, separator, quoted) = match children.last() {
+			Some(&Node::Pair(ref pair)) =>
+				(indentation(&pair.prefix).into(), pair.separator.clone(), pair.key.is_quoted()),
+
+			Some(node) =>
+				(indentation(node.prefix()).into(), "\t\t".into(), node.key().is_quoted()),
+
+			None =>
+				(indent, "\t\t".into(), true),
+		};
+
+		if let Some(node) = children.last_mut() {
+			let suffix = node.suffix_mut();
+
+			if !suffix.ends_with('\n') {
+				suffix.push_str(&newline);
+			}
+		}
+
+		children.push(Node::Pair(Pair {
+			prefix:    indent,
+			key:       Text::new(key, quoted),
+			separator: separator,
+			value:     Text::new(value, quoted),
+			condition: None,
+			suffix:    newline,
+		}));
+
+		Ok(())
+	}
+
+	/// Remove the node at the path, with the comments before it.
+	pub fn remove<S: AsRef<str>>(&mut self, path: S) -> Res<Node> {
+		let (parent, index) = try!(self.parent(path.as_ref()));
+		Ok(parent.remove(index))
+	}
+
+	/// Find the nodes containing the node at the path, and its index.
+	fn parent(&mut self, path: &str) -> Res<(&mut Vec<Node>, usize)> {
+		let indices = try!(locate(&self.children, path).ok_or(Error::NotFound));
+		let (&last, rest) = indices.split_last().unwrap();
+
+		fn walk<'a>(children: &'a mut Vec<Node>, indices: &[usize]) -> &'a mut Vec<Node> {
+			match indices.split_first() {
+				None =>
+					children,
+
+				Some((&index, rest)) =>
+					match children[index] {
+						Node::Group(ref mut group) => walk(&mut group.children, rest),
+						Node::Pair(..)             => unreachable!(),
+					}
+			}
+		}
+
+		Ok((walk(&mut self.children, rest), last))
+	}
+}
+
+impl fmt::Display for Document {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		for child in &self.children {
+			try!(write!(f, "{}", child));
+		}
+
+		f.write_str(&self.trailing)
+	}
+}
+
+/// Resolve a path to the indices of the nodes.
+fn locate(children: &[Node], path: &str) -> Option<Vec<usize>> {
+	let mut indices  = Vec::new();
+	let mut current  = children;
+	let mut segments = path.split('.').map(|s| s.trim()).peekable();
+
+	while let Some(name) = segments.next() {
+		let matches = current.iter().enumerate()
+			.filter(|&(_, node)| node.key().value() == name)
+			.map(|(index, _)| index)
+			.collect::<Vec<_>>();
+
+		let index = match matches.len() {
+			0 => return None,
+			1 => matches[0],
+
+			_ => match segments.next().and_then(|s| s.parse::<usize>().ok()).and_then(|i| matches.get(i)) {
+				Some(&index) => index,
+				None         => return None,
+			}
+		};
+
+		indices.push(index);
+
+		if segments.peek().is_some() {
+			current = match current[index] {
+				Node::Group(ref group) => &group.children,
+				Node::Pair(..)         => return None,
+			};
+		}
+	}
+
+	Some(indices)
+}
+
+/// The indentation of the last line.
+fn indentation(value: &str) -> &str {
+	&value[value.rfind('\n').map(|i| i + 1).unwrap_or(0) ..]
+}
+
+struct Lexer<'a> {
+	source:   &'a str,
+	position: usize,
+}
+
+impl<'a> Lexer<'a> {
+	fn rest(&self) -> &'a str {
+		&self.source[self.position ..]
+	}
+
+	fn peek(&self) -> Option<u8> {
+		self.source.as_bytes().get(self.position).cloned()
+	}
+
+	fn take<F: Fn(u8) -> bool>(&mut self, predicate: F) -> &'a str {
+		let start = self.position;
+
+		while self.peek().map_or(false, |b| predicate(b)) {
+			self.position += 1;
+		}
+
+		&self.source[start .. self.position]
+	}
+
+	/// Whitespace and comments.
+	fn trivia(&mut self) -> String {
+		let start = self.position;
+
+		loop {
+			match self.peek() {
+				Some(b' ') | Some(b'\t') | Some(b'\r') | Some(b'\n') =>
+					self.position += 1,
+
+				Some(b'/') if self.rest().starts_with("//") => {
+					self.take(|b| b != b'\n');
+				}
+
+				_ =>
+					break
+			}
+		}
+
+		self.source[start .. self.position].into()
+	}
+
+	/// Whitespace and a comment up to the end of the line.
+	fn suffix(&mut self) -> String {
+		let start = self.position;
+		self.take(|b| b == b' ' || b == b'\t');
+
+		if self.rest().starts_with("//") {
+			self.take(|b| b != b'\n');
+		}
+
+		if self.rest().starts_with("\r\n") {
+			self.position += 2;
+		}
+		else if self.rest().starts_with('\n') {
+			self.position += 1;
+		}
+		else if !self.rest().is_empty() && !self.source[start .. self.position].contains("//") {
+			self.position = start;
+		}
+
+		self.source[start .. self.position].into()
+	}
+
+	fn text(&mut self) -> Res<Text> {
+		let start = self.position;
+
+		match self.peek() {
+			Some(b'"') => {
+				self.position += 1;
+
+				loop {
+					match self.peek() {
+						Some(b'\\') => self.position += 2,
+						Some(b'"')  => { self.position += 1; break }
+						Some(_)     => self.position += 1,
+						None        => return Err(Error::Parse),
+					}
+				}
+			}
+
+			Some(b'{') | Some(b'}') | None =>
+				return Err(Error::Parse),
+
+			Some(_) => {
+				self.take(|b| match b { b' ' | b'\t' | b'\r' | b'\n' | b'{' | b'}' | b'"' => false, _ => true });
+			}
+		}
+
+		Ok(Text { raw: self.source[start .. self.position].into() })
+	}
+
+	fn condition(&mut self, before: String) -> Res<Condition> {
+		let start = self.position;
+		self.take(|b| b != b']' && b != b'\n');
+
+		if self.peek() != Some(b']') {
+			return Err(Error::Parse);
+		}
+
+		self.position += 1;
+
+		Ok(Condition {
+			before: before,
+			raw:    self.source[start .. self.position].into(),
+		})
+	}
+
+	/// Parse the nodes up to the end of the group, or of the source.
+	fn nodes(&mut self, top: bool) -> Res<(Vec<Node>, String)> {
+		let mut nodes = Vec::new();
+
+		loop {
+			let prefix = self.trivia();
+
+			match self.peek() {
+				None if top =>
+					return Ok((nodes, prefix)),
+
+				Some(b'}') if !top => {
+					self.position += 1;
+					return Ok((nodes, prefix));
+				}
+
+				None | Some(b'}') =>
+					return Err(Error::Parse),
+
+				_ =>
+					()
+			}
+
+			let key = try!(self.text());
+			let gap = self.trivia();
+
+			let (condition, gap) = if self.peek() == Some(b'[') {
+				(Some(try!(self.condition(gap))), self.trivia())
+			}
+			else {
+				(None, gap)
+			};
+
+			if self.peek() == Some(b'{') {
+				self.position += 1;
+
+				let opened            = self.suffix();
+				let (children, close) = try!(self.nodes(false));
+				let suffix            = self.suffix();
+
+				nodes.push(Node::Group(Group {
+					prefix:    prefix,
+					key:       key,
+					condition: condition,
+					open:      gap,
+					opened:    opened,
+					children:  children,
+					close:     close,
+					suffix:    suffix,
+				}));
+			}
+			else {
+				if condition.is_some() {
+					return Err(Error::Parse);
+				}
+
+				let value = try!(self.text());
+				let start = self.position;
+				let space = self.take(|b| b == b' ' || b == b'\t').into();
+
+				let condition = if self.peek() == Some(b'[') {
+					Some(try!(self.condition(space)))
+				}
+				else {
+					self.position = start;
+					None
+				};
+
+				nodes.push(Node::Pair(Pair {
+					prefix:    prefix,
+					key:       key,
+					separator: gap,
+					value:     value,
+					condition: condition,
+					suffix:    self.suffix(),
+				}));
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Document, Node};
+
+	const SOURCE: &'static str = "// Settings.\n\"root\"\n{\n\t\"name\"\t\t\"value\" // trailing\n\n\t// Only on Windows.\n\tbare\tword [$WIN32]\n\t\"group\" [!$X360]\n\t{\n\t}\n\t\"group\"\n\t{\n\t\t\"id\"\t\t\"1\"\n\t}\n}\n";
+
+	#[test]
+	fn lossless() {
+		let document = Document::parse(SOURCE).unwrap();
+		assert_eq!(SOURCE, document.to_string());
+
+		match document.get("root.bare").unwrap() {
+			&Node::Pair(ref pair) => {
+				assert_eq!("word", pair.value().value());
+				assert_eq!("$WIN32", pair.condition().unwrap().expression());
+			}
+
+			_ => panic!()
+		}
+
+		assert!(document.get("root.group").is_none());
+		assert!(document.get("root.group.1.id").is_some());
+		assert!(Document::parse("\"unterminated").is_err());
+		assert!(Document::parse("\"key\" {").is_err());
+	}
+
+	#[test]
+	fn edit() {
+		let mut document = Document::parse(SOURCE).unwrap();
+
+		document.set("root.name", "other \"one\"").unwrap();
+		document.set("root.bare", "two words").unwrap();
+		document.remove("root.group.1").unwrap();
+		document.insert("root.group", "added", "yes").unwrap();
+		document.insert("root", "last", "1").unwrap();
+
+		assert!(document.set("root.missing", "").is_err());
+
+		assert_eq!("// Settings.\n\"root\"\n{\n\t\"name\"\t\t\"other \\\"one\\\"\" // trailing\n\n\t// Only on Windows.\n\tbare\t\"two words\" [$WIN32]\n\t\"group\" [!$X360]\n\t{\n\t\t\"added\"\t\t\"yes\"\n\t}\n\t\"last\"\t\t\"1\"\n}\n",
+			document.to_string());
+	}
+}
@@ -2,9 +2,16 @@ use std::ops::{Deref, DerefMut};
 use super::Entry;
 
 /// An array of entries (items that have the same key).
-#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
 pub struct Array(Vec<Entry>);
 
+impl Array {
+	/// Create an empty array.
+	pub fn new() -> Array {
+		Array::default()
+	}
+}
+
 impl From<Entry> for Array {
 	fn from(value: Entry) -> Self {
 		Array(vec![value])
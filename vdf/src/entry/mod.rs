@@ -161,4 +161,4 @@ mod statement;
 pub use self::statement::Statement;
 
 mod value;
-pub use self::value::Value;
+pub use self::value::{Value, Kind};
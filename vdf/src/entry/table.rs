@@ -1,35 +1,30 @@
-use std::ops::Deref;
 use std::io::{Read};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::Entry as Slot;
 use reader::{Reader, Event, Item};
 use super::{Entry, Statement, Value, Array};
 use {Result as Res};
 
-/// A table of entries.
-#[derive(Clone, PartialEq, Eq, Debug)]
-pub struct Table(HashMap<String, Entry>);
-
-fn insert(map: &mut HashMap<String, Entry>, key: String, value: Entry) {
-	if !map.contains_key(&key) {
-		map.insert(key, value);
-		return;
-	}
-
-	if let Some(&mut Entry::Array(ref mut array)) = map.get_mut(&key) {
-		array.push(value);
-		return;
-	}
-
-	let mut array = Array::from(map.remove(&key).unwrap());
-	array.push(value);
-
-	map.insert(key, array.into());
+/// A table of entries, keeping the order they were inserted in.
+///
+/// Repeated keys are collapsed into an `Array` for lookups, but every
+/// occurrence keeps its own position so they can be written back where they
+/// were; equality ignores the order.
+#[derive(Clone, Default, Debug)]
+pub struct Table {
+	map:   HashMap<String, Entry>,
+	order: Vec<String>,
 }
 
 impl Table {
+	/// Create an empty table.
+	pub fn new() -> Table {
+		Table::default()
+	}
+
 	/// Load a table from the given `Reader`.
 	pub fn load<R: Read>(reader: &mut Reader<R>) -> Res<Table> {
-		let mut map = HashMap::new();
+		let mut table = Table::new();
 
 		loop {
 			match try!(reader.event()) {
@@ -37,33 +32,156 @@ impl Table {
 					(),
 
 				Event::Entry(Item::Value(key), Item::Statement(value)) =>
-					insert(&mut map, key, Statement::from(value).into()),
+					table.push(key, Statement::from(value)),
 
 				Event::Entry(Item::Value(key), Item::Value(value)) =>
-					insert(&mut map, key, Value::from(value).into()),
+					table.push(key, Value::from(value)),
 
 				Event::GroupStart(name) =>
-					insert(&mut map, name, try!(Table::load(reader)).into()),
+					table.push(name, try!(Table::load(reader))),
 
 				Event::GroupEnd | Event::End =>
 					break
 			}
 		}
 
-		return Ok(Table(map));
+		Ok(table)
+	}
+
+	/// Add an entry at the end, if the key is already present the values are
+	/// collapsed into an `Array`.
+	pub fn push<K: Into<String>, V: Into<Entry>>(&mut self, key: K, value: V) {
+		let key   = key.into();
+		let value = value.into();
+
+		self.order.push(key.clone());
+
+		match self.map.entry(key) {
+			Slot::Vacant(slot) => {
+				slot.insert(value);
+			}
+
+			Slot::Occupied(mut slot) => {
+				if let &mut Entry::Array(ref mut array) = slot.get_mut() {
+					array.push(value);
+					return;
+				}
+
+				let mut array = Array::from(slot.get().clone());
+				array.push(value);
+
+				slot.insert(array.into());
+			}
+		}
+	}
+
+	/// Insert an entry, replacing the previous value in place or adding it at
+	/// the end.
+	pub fn insert<K: Into<String>, V: Into<Entry>>(&mut self, key: K, value: V) -> Option<Entry> {
+		let key = key.into();
+
+		if !self.map.contains_key(&key) {
+			self.order.push(key.clone());
+		}
+
+		self.map.insert(key, value.into())
+	}
+
+	/// Remove every occurrence of the key.
+	pub fn remove(&mut self, key: &str) -> Option<Entry> {
+		self.order.retain(|k| k != key);
+		self.map.remove(key)
+	}
+
+	/// Get the entry for the key.
+	pub fn get(&self, key: &str) -> Option<&Entry> {
+		self.map.get(key)
+	}
+
+	/// Get the entry for the key mutably.
+	pub fn get_mut(&mut self, key: &str) -> Option<&mut Entry> {
+		self.map.get_mut(key)
+	}
+
+	/// Check if the key is present.
+	pub fn contains_key(&self, key: &str) -> bool {
+		self.map.contains_key(key)
+	}
+
+	/// The number of distinct keys.
+	pub fn len(&self) -> usize {
+		self.map.len()
+	}
+
+	/// Check if the table is empty.
+	pub fn is_empty(&self) -> bool {
+		self.map.is_empty()
+	}
+
+	/// Iterate over the distinct keys in the order they first appeared.
+	pub fn keys<'a>(&'a self) -> Box<Iterator<Item = &'a String> + 'a> {
+		let mut seen = HashSet::new();
+
+		Box::new(self.order.iter().filter(move |&key|
+			seen.insert(key)))
+	}
+
+	/// Iterate over the distinct keys and their entries, in the order they
+	/// first appeared.
+	pub fn iter<'a>(&'a self) -> Box<Iterator<Item = (&'a String, &'a Entry)> + 'a> {
+		Box::new(self.keys().map(move |key|
+			(key, &self.map[key])))
+	}
+
+	/// Iterate over every occurrence of every key in order, with arrays split
+	/// back into their elements.
+	pub fn entries(&self) -> Vec<(&str, &Entry)> {
+		let mut total = HashMap::new();
+		for key in &self.order {
+			*total.entry(&key[..]).or_insert(0) += 1;
+		}
+
+		let mut seen    = HashMap::new();
+		let mut entries = Vec::new();
+
+		for key in &self.order {
+			let index = {
+				let index = seen.entry(&key[..]).or_insert(0);
+				*index += 1;
+				*index - 1
+			};
+
+			let last = index + 1 == total[&key[..]];
+
+			match self.map[key] {
+				Entry::Array(ref array) if last =>
+					entries.extend(array.iter().skip(index).map(|entry| (&key[..], entry))),
+
+				Entry::Array(ref array) =>
+					entries.extend(array.get(index).map(|entry| (&key[..], entry))),
+
+				ref entry if index == 0 =>
+					entries.push((&key[..], entry)),
+
+				_ =>
+					()
+			}
+		}
+
+		entries
 	}
 }
 
-impl Into<Entry> for Table {
-	fn into(self) -> Entry {
-		Entry::Table(self)
+impl PartialEq for Table {
+	fn eq(&self, other: &Table) -> bool {
+		self.map == other.map
 	}
 }
 
-impl Deref for Table {
-	type Target = HashMap<String, Entry>;
+impl Eq for Table { }
 
-	fn deref(&self) -> &Self::Target {
-		&self.0
+impl Into<Entry> for Table {
+	fn into(self) -> Entry {
+		Entry::Table(self)
 	}
 }
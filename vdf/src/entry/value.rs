@@ -1,15 +1,66 @@
 use std::ops::Deref;
 use super::{Entry, Parse};
 
-#[derive(Clone, PartialEq, Eq, Debug)]
-pub struct Value(String);
+/// The type of a value, only binary files store it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Kind {
+	/// An UTF-8 string, what text files contain.
+	String,
+
+	/// An UTF-16 string.
+	WideString,
+
+	/// A signed 32 bits integer.
+	Int32,
+
+	/// A 32 bits float.
+	Float,
+
+	/// A pointer, stored as a signed 32 bits integer.
+	Pointer,
+
+	/// A color, stored as an unsigned 32 bits integer.
+	Color,
+
+	/// An unsigned 64 bits integer.
+	UInt64,
+
+	/// A signed 64 bits integer.
+	Int64,
+}
+
+/// A value, values are equal when their text is, the kind is only kept to
+/// write binary files back.
+#[derive(Clone, Eq, Debug)]
+pub struct Value {
+	string: String,
+	kind:   Kind,
+}
+
+impl PartialEq for Value {
+	fn eq(&self, other: &Value) -> bool {
+		self.string == other.string
+	}
+}
 
 impl From<String> for Value {
 	fn from(value: String) -> Value {
-		Value(value)
+		Value::new(value, Kind::String)
 	}
 }
 
+macro_rules! from {
+	($($ty:ty => $kind:ident),*) => ($(
+		impl From<$ty> for Value {
+			fn from(value: $ty) -> Value {
+				Value::new(value.to_string(), Kind::$kind)
+			}
+		}
+	)*);
+}
+
+from!(i32 => Int32, f32 => Float, u64 => UInt64, i64 => Int64);
+
 impl Into<Entry> for Value {
 	fn into(self) -> Entry {
 		Entry::Value(self)
@@ -20,13 +71,26 @@ impl Deref for Value {
 	type Target = str;
 
 	fn deref(&self) -> &Self::Target {
-		&self.0
+		&self.string
 	}
 }
 
 impl Value {
+	/// Create a value of the given kind from its textual form.
+	pub fn new<S: Into<String>>(value: S, kind: Kind) -> Value {
+		Value {
+			string: value.into(),
+			kind:   kind,
+		}
+	}
+
+	/// The type of the value.
+	pub fn kind(&self) -> Kind {
+		self.kind
+	}
+
 	/// Try to convert the value to the given type.
 	pub fn to<T: Parse>(&self) -> Option<T> {
-		T::parse(&self.0)
+		T::parse(&self.string)
 	}
 }
@@ -7,6 +7,8 @@ pub enum Error {
 	Io(io::Error),
 	Parse,
 	Eof,
+	NotFound,
+	Message(String),
 }
 
 impl From<io::Error> for Error {
@@ -32,6 +34,12 @@ impl error::Error for Error {
 
 			&Error::Eof =>
 				"EOF reached.",
+
+			&Error::NotFound =>
+				"Entry not found.",
+
+			&Error::Message(ref message) =>
+				message,
 		}
 	}
 }
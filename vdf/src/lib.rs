@@ -1,7 +1,15 @@
 #[macro_use]
 extern crate nom;
+extern crate byteorder;
 
-use std::io::{Read};
+#[cfg(feature = "serde")]
+extern crate serde;
+
+#[cfg(all(test, feature = "serde"))]
+#[macro_use]
+extern crate serde_derive;
+
+use std::io::{Read, Write};
 use std::fs::File;
 use std::path::Path;
 
@@ -13,11 +21,29 @@ pub type Result<T> = ::std::result::Result<T, Error>;
 pub mod reader;
 pub use reader::Reader;
 
+pub mod writer;
+pub use writer::Writer;
+
+pub mod document;
+pub use document::Document;
+
 pub mod entry;
-pub use entry::{Table, Entry, Statement, Value};
+pub use entry::{Table, Entry, Statement, Value, Kind};
 
 pub mod parser;
 
+pub mod binary;
+
+#[cfg(feature = "serde")]
+pub mod de;
+#[cfg(feature = "serde")]
+pub use de::{from_entry, from_reader, from_str};
+
+#[cfg(feature = "serde")]
+pub mod ser;
+#[cfg(feature = "serde")]
+pub use ser::{to_entry, to_writer, to_string};
+
 /// Create a reader from the given path.
 pub fn open<P: AsRef<Path>>(path: P) -> Result<Reader<File>> {
 	Ok(Reader::from(try!(File::open(path))))
@@ -32,3 +58,18 @@ pub fn read<R: Read>(stream: R) -> Result<Reader<R>> {
 pub fn load<P: AsRef<Path>>(path: P) -> Result<Entry> {
 	Ok(try!(Table::load(&mut try!(open(path)))).into())
 }
+
+/// Create a writer to the given path.
+pub fn create<P: AsRef<Path>>(path: P) -> Result<Writer<File>> {
+	Ok(Writer::from(try!(File::create(path))))
+}
+
+/// Create a writer to the given stream.
+pub fn write<W: Write>(stream: W) -> Result<Writer<W>> {
+	Ok(Writer::from(stream))
+}
+
+/// Save a table to the given path.
+pub fn save<P: AsRef<Path>>(path: P, table: &Table) -> Result<()> {
+	try!(create(path)).table(table)
+}
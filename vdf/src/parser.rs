@@ -17,7 +17,8 @@ pub enum Token<'a> {
 	Statement(Cow<'a, str>),
 }
 
-fn string(buffer: &[u8]) -> Result<Cow<str>, Utf8Error> {
+/// Unescape a string.
+pub fn string(buffer: &[u8]) -> Result<Cow<str>, Utf8Error> {
 	if buffer.iter().any(|&b| b == b'\\') {
 		let mut buffer = buffer.iter().cloned();
 		let mut string = Vec::with_capacity(buffer.len());
@@ -77,7 +78,7 @@ named!(pub enclosed(&[u8]) -> Token,
 	alt!(enclosed_statement | enclosed_item));
 
 named!(enclosed_content,
-	escaped!(is_not!("\"\\"), '\\', is_a_bytes!(&b"\"n\\"[..])));
+	escaped!(is_not!("\"\\"), '\\', one_of!("\"ntr\\")));
 
 named!(enclosed_statement(&[u8]) -> Token,
 	map_res!(delimited!(char!('"'), chain!(char!('#') ~ value: enclosed_content, || { value }), char!('"')),
@@ -124,6 +125,8 @@ mod tests {
 
 		assert_eq!(super::enclosed(b"\"te\\\"st\""), Done(&b""[..], Token::Item("te\"st".into())));
 		assert_eq!(super::enclosed(b"\"#te\\\"st\""), Done(&b""[..], Token::Statement("te\"st".into())));
+
+		assert_eq!(super::enclosed(b"\"te\\tst\""), Done(&b""[..], Token::Item("te\tst".into())));
 	}
 
 	#[test]
@@ -59,6 +59,7 @@ pub struct Reader<R: Read> {
 	stream:   BufReader<R>,
 	buffer:   Vec<u8>,
 	consumed: usize,
+	eof:      bool,
 }
 
 impl<R: Read> From<R> for Reader<R> {
@@ -67,6 +68,7 @@ impl<R: Read> From<R> for Reader<R> {
 			stream:   BufReader::new(stream),
 			buffer:   Vec::new(),
 			consumed: 0,
+			eof:      false,
 		}
 	}
 }
@@ -88,15 +90,23 @@ impl<R: Read> Reader<R> {
 				Incomplete(Needed::Unknown) =>
 					64,
 
+				// A bare item could go on past the end of the buffer.
+				Done(rest, _) if rest.is_empty() && !self.eof =>
+					64,
+
 				Done(rest, _) => {
 					self.consumed = self.buffer.len() - rest.len();
 					break;
 				}
 			};
 
-			if try!(self.stream.by_ref().take(needed as u64).read_to_end(&mut self.buffer)) == 0 {
+			if self.eof {
 				return Err(Error::Eof);
 			}
+
+			if try!(self.stream.by_ref().take(needed as u64).read_to_end(&mut self.buffer)) == 0 {
+				self.eof = true;
+			}
 		}
 
 		Ok(())
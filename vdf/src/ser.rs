@@ -0,0 +1,311 @@
+//! Serialization with serde.
+//!
+//! Structs and maps become tables, sequences become repeated keys, booleans
+//! are written as `"0"` and `"1"`, and `None` is skipped; the numeric types
+//! binary files support keep their kind.
+
+use std::fmt::Display;
+use std::io::Write;
+use serde::ser::{self, Serialize};
+use entry::{Entry, Table, Array, Value};
+use writer::Writer;
+use {Result as Res, Error};
+
+impl ser::Error for Error {
+	fn custom<T: Display>(message: T) -> Self {
+		Error::Message(message.to_string())
+	}
+}
+
+/// Serialize a value to an entry, `None` if there is nothing to write.
+pub fn to_entry<T: Serialize + ?Sized>(value: &T) -> Res<Option<Entry>> {
+	value.serialize(Serializer)
+}
+
+/// Serialize a value to the given stream, the value must serialize to a
+/// table.
+pub fn to_writer<W: Write, T: Serialize + ?Sized>(stream: W, value: &T) -> Res<()> {
+	match try!(to_entry(value)) {
+		Some(Entry::Table(table)) =>
+			Writer::from(stream).table(&table),
+
+		_ =>
+			Err(Error::Message("the top level must be a table".into()))
+	}
+}
+
+/// Serialize a value to a string, the value must serialize to a table.
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Res<String> {
+	let mut buffer = Vec::new();
+	try!(to_writer(&mut buffer, value));
+
+	Ok(String::from_utf8(buffer).unwrap())
+}
+
+fn value<T: ToString>(value: T) -> Res<Option<Entry>> {
+	Ok(Some(Entry::Value(Value::from(value.to_string()))))
+}
+
+fn typed<T: Into<Value>>(value: T) -> Res<Option<Entry>> {
+	Ok(Some(Entry::Value(value.into())))
+}
+
+fn key(value: Option<Entry>) -> Res<String> {
+	match value {
+		Some(Entry::Value(value)) =>
+			Ok((*value).into()),
+
+		_ =>
+			Err(Error::Message("keys must be values".into()))
+	}
+}
+
+fn variant(name: &'static str, value: Option<Entry>) -> Res<Option<Entry>> {
+	let mut table = Table::new();
+
+	if let Some(value) = value {
+		table.insert(name, value);
+	}
+
+	Ok(Some(Entry::Table(table)))
+}
+
+/// A serializer to entries.
+pub struct Serializer;
+
+impl ser::Serializer for Serializer {
+	type Ok    = Option<Entry>;
+	type Error = Error;
+
+	type SerializeSeq           = Sequence;
+	type SerializeTuple         = Sequence;
+	type SerializeTupleStruct   = Sequence;
+	type SerializeTupleVariant  = Sequence;
+	type SerializeMap           = Map;
+	type SerializeStruct        = Map;
+	type SerializeStructVariant = Map;
+
+	fn serialize_bool(self, v: bool) -> Res<Self::Ok> {
+		value(if v { "1" } else { "0" })
+	}
+
+	fn serialize_i8(self, v: i8) -> Res<Self::Ok> { value(v) }
+	fn serialize_i16(self, v: i16) -> Res<Self::Ok> { value(v) }
+	fn serialize_i32(self, v: i32) -> Res<Self::Ok> { typed(v) }
+	fn serialize_i64(self, v: i64) -> Res<Self::Ok> { typed(v) }
+
+	fn serialize_u8(self, v: u8) -> Res<Self::Ok> { value(v) }
+	fn serialize_u16(self, v: u16) -> Res<Self::Ok> { value(v) }
+	fn serialize_u32(self, v: u32) -> Res<Self::Ok> { value(v) }
+	fn serialize_u64(self, v: u64) -> Res<Self::Ok> { typed(v) }
+
+	fn serialize_f32(self, v: f32) -> Res<Self::Ok> { typed(v) }
+	fn serialize_f64(self, v: f64) -> Res<Self::Ok> { value(v) }
+
+	fn serialize_char(self, v: char) -> Res<Self::Ok> { value(v) }
+	fn serialize_str(self, v: &str) -> Res<Self::Ok> { value(v) }
+
+	fn serialize_bytes(self, v: &[u8]) -> Res<Self::Ok> {
+		value(String::from_utf8_lossy(v))
+	}
+
+	fn serialize_none(self) -> Res<Self::Ok> {
+		Ok(None)
+	}
+
+	fn serialize_some<T: Serialize + ?Sized>(self, v: &T) -> Res<Self::Ok> {
+		v.serialize(self)
+	}
+
+	fn serialize_unit(self) -> Res<Self::Ok> {
+		Ok(None)
+	}
+
+	fn serialize_unit_struct(self, _name: &'static str) -> Res<Self::Ok> {
+		Ok(None)
+	}
+
+	fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Res<Self::Ok> {
+		value(variant)
+	}
+
+	fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, v: &T) -> Res<Self::Ok> {
+		v.serialize(self)
+	}
+
+	fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _name: &'static str, _index: u32, variant: &'static str, v: &T) -> Res<Self::Ok> {
+		self::variant(variant, try!(v.serialize(Serializer)))
+	}
+
+	fn serialize_seq(self, _len: Option<usize>) -> Res<Self::SerializeSeq> {
+		Ok(Sequence { variant: None, array: Array::new() })
+	}
+
+	fn serialize_tuple(self, len: usize) -> Res<Self::SerializeTuple> {
+		self.serialize_seq(Some(len))
+	}
+
+	fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Res<Self::SerializeTupleStruct> {
+		self.serialize_seq(Some(len))
+	}
+
+	fn serialize_tuple_variant(self, _name: &'static str, _index: u32, variant: &'static str, _len: usize) -> Res<Self::SerializeTupleVariant> {
+		Ok(Sequence { variant: Some(variant), array: Array::new() })
+	}
+
+	fn serialize_map(self, _len: Option<usize>) -> Res<Self::SerializeMap> {
+		Ok(Map { variant: None, table: Table::new(), key: None })
+	}
+
+	fn serialize_struct(self, _name: &'static str, _len: usize) -> Res<Self::SerializeStruct> {
+		self.serialize_map(None)
+	}
+
+	fn serialize_struct_variant(self, _name: &'static str, _index: u32, variant: &'static str, _len: usize) -> Res<Self::SerializeStructVariant> {
+		Ok(Map { variant: Some(variant), table: Table::new(), key: None })
+	}
+}
+
+#[doc(hidden)]
+pub struct Sequence {
+	variant: Option<&'static str>,
+	array:   Array,
+}
+
+impl Sequence {
+	fn push<T: Serialize + ?Sized>(&mut self, v: &T) -> Res<()> {
+		if let Some(entry) = try!(v.serialize(Serializer)) {
+			self.array.push(entry);
+		}
+
+		Ok(())
+	}
+
+	fn end(self) -> Res<Option<Entry>> {
+		match self.variant {
+			Some(name) => variant(name, Some(self.array.into())),
+			None       => Ok(Some(self.array.into())),
+		}
+	}
+}
+
+impl ser::SerializeSeq for Sequence {
+	type Ok    = Option<Entry>;
+	type Error = Error;
+
+	fn serialize_element<T: Serialize + ?Sized>(&mut self, v: &T) -> Res<()> {
+		self.push(v)
+	}
+
+	fn end(self) -> Res<Self::Ok> {
+		Sequence::end(self)
+	}
+}
+
+impl ser::SerializeTuple for Sequence {
+	type Ok    = Option<Entry>;
+	type Error = Error;
+
+	fn serialize_element<T: Serialize + ?Sized>(&mut self, v: &T) -> Res<()> {
+		self.push(v)
+	}
+
+	fn end(self) -> Res<Self::Ok> {
+		Sequence::end(self)
+	}
+}
+
+impl ser::SerializeTupleStruct for Sequence {
+	type Ok    = Option<Entry>;
+	type Error = Error;
+
+	fn serialize_field<T: Serialize + ?Sized>(&mut self, v: &T) -> Res<()> {
+		self.push(v)
+	}
+
+	fn end(self) -> Res<Self::Ok> {
+		Sequence::end(self)
+	}
+}
+
+impl ser::SerializeTupleVariant for Sequence {
+	type Ok    = Option<Entry>;
+	type Error = Error;
+
+	fn serialize_field<T: Serialize + ?Sized>(&mut self, v: &T) -> Res<()> {
+		self.push(v)
+	}
+
+	fn end(self) -> Res<Self::Ok> {
+		Sequence::end(self)
+	}
+}
+
+#[doc(hidden)]
+pub struct Map {
+	variant: Option<&'static str>,
+	table:   Table,
+	key:     Option<String>,
+}
+
+impl Map {
+	fn insert<T: Serialize + ?Sized>(&mut self, key: String, v: &T) -> Res<()> {
+		if let Some(entry) = try!(v.serialize(Serializer)) {
+			self.table.insert(key, entry);
+		}
+
+		Ok(())
+	}
+
+	fn end(self) -> Res<Option<Entry>> {
+		match self.variant {
+			Some(name) => variant(name, Some(self.table.into())),
+			None       => Ok(Some(self.table.into())),
+		}
+	}
+}
+
+impl ser::SerializeMap for Map {
+	type Ok    = Option<Entry>;
+	type Error = Error;
+
+	fn serialize_key<T: Serialize + ?Sized>(&mut self, k: &T) -> Res<()> {
+		self.key = Some(try!(key(try!(k.serialize(Serializer)))));
+		Ok(())
+	}
+
+	fn serialize_value<T: Serialize + ?Sized>(&mut self, v: &T) -> Res<()> {
+		let key = try!(self.key.take().ok_or_else(|| Error::Message("value without a key".into())));
+		self.insert(key, v)
+	}
+
+	fn end(self) -> Res<Self::Ok> {
+		Map::end(self)
+	}
+}
+
+impl ser::SerializeStruct for Map {
+	type Ok    = Option<Entry>;
+	type Error = Error;
+
+	fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, v: &T) -> Res<()> {
+		self.insert(key.into(), v)
+	}
+
+	fn end(self) -> Res<Self::Ok> {
+		Map::end(self)
+	}
+}
+
+impl ser::SerializeStructVariant for Map {
+	type Ok    = Option<Entry>;
+	type Error = Error;
+
+	fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, v: &T) -> Res<()> {
+		self.insert(key.into(), v)
+	}
+
+	fn end(self) -> Res<Self::Ok> {
+		Map::end(self)
+	}
+}
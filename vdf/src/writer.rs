@@ -0,0 +1,265 @@
+use std::io::Write;
+use reader::{Event, Item};
+use entry::{Entry, Table};
+use {Result as Res, Error};
+
+/// When items are enclosed in quotes.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Quote {
+	/// Always enclose items.
+	Always,
+
+	/// Only enclose items that can't be written bare.
+	Needed,
+}
+
+/// A streaming VDF writer.
+pub struct Writer<W: Write> {
+	stream: W,
+	indent:  String,
+	newline: String,
+	quote:   Quote,
+	depth:   usize,
+}
+
+impl<W: Write> From<W> for Writer<W> {
+	fn from(stream: W) -> Writer<W> {
+		Writer {
+			stream:  stream,
+			indent:  "\t".into(),
+			newline: "\n".into(),
+			quote:   Quote::Always,
+			depth:   0,
+		}
+	}
+}
+
+impl<W: Write> Writer<W> {
+	/// Use the given string for every level of indentation.
+	pub fn indent<S: Into<String>>(mut self, value: S) -> Self {
+		self.indent = value.into();
+		self
+	}
+
+	/// Use the given string to end lines.
+	pub fn newline<S: Into<String>>(mut self, value: S) -> Self {
+		self.newline = value.into();
+		self
+	}
+
+	/// Change when items are enclosed in quotes.
+	pub fn quote(mut self, value: Quote) -> Self {
+		self.quote = value;
+		self
+	}
+
+	/// Get the underlying stream.
+	pub fn into_inner(self) -> W {
+		self.stream
+	}
+
+	fn pad(&mut self) -> Res<()> {
+		for _ in 0 .. self.depth {
+			try!(self.stream.write_all(self.indent.as_bytes()));
+		}
+
+		Ok(())
+	}
+
+	fn item(&mut self, value: &str, statement: bool) -> Res<()> {
+		// Quoted or not, it would be read back as a statement.
+		if !statement && value.starts_with('#') {
+			return Err(Error::Message(format!("{:?} starts with `#`, it would be read as a statement", value)));
+		}
+
+		let bare = self.quote == Quote::Needed && !value.is_empty() &&
+			!value.contains(|c| match c { ' ' | '\t' | '\n' | '\r' | '{' | '}' | '"' | '\\' => true, _ => false });
+
+		if !bare {
+			try!(self.stream.write_all(b"\""));
+		}
+
+		if statement {
+			try!(self.stream.write_all(b"#"));
+		}
+
+		try!(self.stream.write_all(escape(value).as_bytes()));
+
+		if !bare {
+			try!(self.stream.write_all(b"\""));
+		}
+
+		Ok(())
+	}
+
+	/// Start a group with the given name.
+	pub fn group_start(&mut self, name: &str) -> Res<()> {
+		try!(self.pad());
+		try!(self.item(name, false));
+		try!(self.stream.write_all(self.newline.as_bytes()));
+		try!(self.pad());
+		try!(self.stream.write_all(b"{"));
+		try!(self.stream.write_all(self.newline.as_bytes()));
+
+		self.depth += 1;
+
+		Ok(())
+	}
+
+	/// End the current group.
+	pub fn group_end(&mut self) -> Res<()> {
+		self.depth = self.depth.saturating_sub(1);
+
+		try!(self.pad());
+		try!(self.stream.write_all(b"}"));
+		try!(self.stream.write_all(self.newline.as_bytes()));
+
+		Ok(())
+	}
+
+	/// Write a key/value pair.
+	pub fn pair(&mut self, key: &Item, value: &Item) -> Res<()> {
+		try!(self.pad());
+		try!(self.item(key, is_statement(key)));
+		try!(self.stream.write_all(b"\t\t"));
+		try!(self.item(value, is_statement(value)));
+		try!(self.stream.write_all(self.newline.as_bytes()));
+
+		Ok(())
+	}
+
+	/// Write a reader event.
+	pub fn event(&mut self, event: &Event) -> Res<()> {
+		match event {
+			&Event::GroupStart(ref name) =>
+				self.group_start(name),
+
+			&Event::GroupEnd =>
+				self.group_end(),
+
+			&Event::Entry(ref key, ref value) =>
+				self.pair(key, value),
+
+			&Event::End =>
+				Ok(try!(self.stream.flush())),
+		}
+	}
+
+	/// Write an entry with the given key, arrays are written as repeated
+	/// keys.
+	pub fn entry(&mut self, key: &str, value: &Entry) -> Res<()> {
+		match value {
+			&Entry::Table(ref table) => {
+				try!(self.group_start(key));
+				try!(self.table(table));
+				try!(self.group_end());
+			}
+
+			&Entry::Array(ref array) =>
+				for value in array.iter() {
+					try!(self.entry(key, value));
+				},
+
+			&Entry::Statement(ref value) =>
+				try!(self.pair(&Item::Value(key.into()), &Item::Statement((**value).into()))),
+
+			&Entry::Value(ref value) =>
+				try!(self.pair(&Item::Value(key.into()), &Item::Value((**value).into()))),
+		}
+
+		Ok(())
+	}
+
+	/// Write the entries of a table.
+	pub fn table(&mut self, table: &Table) -> Res<()> {
+		for (key, value) in table.entries() {
+			try!(self.entry(key, value));
+		}
+
+		Ok(())
+	}
+}
+
+fn is_statement(item: &Item) -> bool {
+	if let &Item::Statement(..) = item { true } else { false }
+}
+
+/// Escape a string, the inverse of what the parser does.
+pub fn escape(value: &str) -> String {
+	let mut result = String::with_capacity(value.len());
+
+	for ch in value.chars() {
+		match ch {
+			'\\' => result.push_str("\\\\"),
+			'\n' => result.push_str("\\n"),
+			'\t' => result.push_str("\\t"),
+			'\r' => result.push_str("\\r"),
+			'"'  => result.push_str("\\\""),
+			ch   => result.push(ch),
+		}
+	}
+
+	result
+}
+
+#[cfg(test)]
+mod tests {
+	use reader::{Reader, Event, Item};
+	use entry::{Entry, Table, Value};
+	use super::{Writer, Quote};
+
+	fn write<F: FnOnce(&mut Writer<Vec<u8>>)>(writer: Writer<Vec<u8>>, body: F) -> String {
+		let mut writer = writer;
+		body(&mut writer);
+
+		String::from_utf8(writer.into_inner()).unwrap()
+	}
+
+	#[test]
+	fn escape() {
+		assert_eq!("a\\\"b\\\\c\\nd\\te", super::escape("a\"b\\c\nd\te"));
+
+		let output = write(Writer::from(Vec::new()), |w|
+			w.pair(&Item::Value("key".into()), &Item::Value("te\"st\t\\".into())).unwrap());
+
+		assert_eq!("\"key\"\t\t\"te\\\"st\\t\\\\\"\n", output);
+
+		let mut reader = Reader::from(output.as_bytes());
+		assert_eq!(Event::Entry(Item::Value("key".into()), Item::Value("te\"st\t\\".into())),
+			reader.event().unwrap());
+	}
+
+	#[test]
+	fn quote() {
+		let output = write(Writer::from(Vec::new()).quote(Quote::Needed).indent("  "), |w| {
+			w.group_start("group").unwrap();
+			w.pair(&Item::Value("bare".into()), &Item::Value("two words".into())).unwrap();
+			w.pair(&Item::Value("key".into()), &Item::Statement("include".into())).unwrap();
+			w.pair(&Item::Value("empty".into()), &Item::Value("".into())).unwrap();
+			w.group_end().unwrap();
+		});
+
+		assert_eq!("group\n{\n  bare\t\t\"two words\"\n  key\t\t#include\n  empty\t\t\"\"\n}\n", output);
+
+		// Values looking like statements can't be written either way.
+		for &quote in &[Quote::Always, Quote::Needed] {
+			let mut writer = Writer::from(Vec::new()).quote(quote);
+			assert!(writer.pair(&Item::Value("key".into()), &Item::Value("#include".into())).is_err());
+			assert!(writer.group_start("#group").is_err());
+		}
+	}
+
+	#[test]
+	fn array() {
+		let mut table = Table::new();
+		let mut array = ::entry::Array::from(Entry::Value(Value::from("1".to_owned())));
+		array.push(Entry::Value(Value::from("2".to_owned())));
+		table.insert("key", array);
+
+		let output = write(Writer::from(Vec::new()), |w|
+			w.table(&table).unwrap());
+
+		assert_eq!("\"key\"\t\t\"1\"\n\"key\"\t\t\"2\"\n", output);
+		assert_eq!(table, Table::load(&mut Reader::from(output.as_bytes())).unwrap());
+	}
+}
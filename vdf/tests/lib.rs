@@ -16,3 +16,57 @@ fn loader() {
 		config.lookup("controller_mappings.group.1.settings.requires_click").unwrap()
 		.to::<bool>().unwrap());
 }
+
+#[test]
+fn writer() {
+	for path in &["tests/desktop.vdf", "tests/complex.vdf", "tests/welp.vdf"] {
+		let config = vdf::load(path).unwrap();
+
+		for quote in &[vdf::writer::Quote::Always, vdf::writer::Quote::Needed] {
+			let mut writer = vdf::write(Vec::new()).unwrap().quote(*quote);
+			writer.table(config.as_table().unwrap()).unwrap();
+
+			let output = writer.into_inner();
+			let loaded = vdf::Table::load(&mut vdf::read(&output[..]).unwrap()).unwrap();
+
+			assert_eq!(config, loaded.into());
+		}
+	}
+}
+
+#[test]
+fn order() {
+	use std::fs::File;
+	use std::io::Read;
+
+	for path in &["tests/desktop.vdf", "tests/complex.vdf", "tests/welp.vdf"] {
+		let mut source = Vec::new();
+		File::open(path).unwrap().read_to_end(&mut source).unwrap();
+
+		let config = vdf::load(path).unwrap();
+		let mut writer = vdf::write(Vec::new()).unwrap().newline("\r\n");
+		writer.table(config.as_table().unwrap()).unwrap();
+
+		assert_eq!(String::from_utf8_lossy(&source), String::from_utf8_lossy(&writer.into_inner()));
+	}
+}
+
+#[test]
+fn document() {
+	use std::fs::File;
+	use std::io::Read;
+
+	for path in &["tests/desktop.vdf", "tests/complex.vdf", "tests/welp.vdf"] {
+		let mut source = String::new();
+		File::open(path).unwrap().read_to_string(&mut source).unwrap();
+
+		let document = vdf::Document::parse(&source).unwrap();
+		assert_eq!(source, document.to_string());
+	}
+
+	let mut document = vdf::Document::load("tests/desktop.vdf").unwrap();
+	document.set("controller_mappings.title", "Edited").unwrap();
+
+	let config = vdf::read(document.to_string().as_bytes()).and_then(|mut r| vdf::Table::load(&mut r)).unwrap();
+	assert_eq!("Edited", vdf::Entry::Table(config).lookup("controller_mappings.title").unwrap().as_str().unwrap());
+}